@@ -0,0 +1,135 @@
+use crate::neighbourhood::Neighbourhood;
+use crate::topology::Topology;
+
+// Rolls are stored one bit per cell, alongside a cached count of occupied
+// neighbours so that accessibility checks never rescan the neighbourhood.
+// On small wrapped grids a stencil may reach the same cell more than once;
+// such cells are counted with multiplicity.
+#[derive(Clone)]
+pub struct RollGrid {
+    topology: Topology,
+    offsets: Vec<[i32; 3]>,
+    occupied: Vec<u64>,
+    neighbor_counts: Vec<u16>,
+}
+
+impl RollGrid {
+    // Blank lines separate the layers of a volumetric grid; input without them
+    // is a single flat layer.
+    pub fn parse(input: &str, neighbourhood: &Neighbourhood, wrap: bool) -> Self {
+        let mut layers: Vec<Vec<&[u8]>> = vec![Vec::new()];
+        for line in input.lines() {
+            if line.is_empty() {
+                if !layers.last().unwrap().is_empty() {
+                    layers.push(Vec::new());
+                }
+            } else {
+                layers.last_mut().unwrap().push(line.as_bytes());
+            }
+        }
+        if layers.len() > 1 && layers.last().unwrap().is_empty() {
+            layers.pop();
+        }
+
+        let rows = layers.iter().map(|layer| layer.len()).max().unwrap_or(0);
+        let cols = layers.iter().flatten().map(|line| line.len()).max().unwrap_or(0);
+        let topology = Topology { shape: [layers.len(), rows, cols], wrap };
+        let cell_count = topology.cell_count();
+        let offsets = neighbourhood.offsets(topology.is_volumetric());
+        assert!(offsets.len() <= u16::MAX as usize, "Neighbourhood too large");
+
+        let mut grid = RollGrid {
+            topology,
+            offsets,
+            occupied: vec![0; cell_count.div_ceil(64)],
+            neighbor_counts: vec![0; cell_count],
+        };
+
+        for (layer, lines) in layers.iter().enumerate() {
+            for (row, line) in lines.iter().enumerate() {
+                for (col, &byte) in line.iter().enumerate() {
+                    if byte == b'@' {
+                        let cell = (layer * rows + row) * cols + col;
+                        grid.occupied[cell / 64] |= 1 << (cell % 64);
+                    }
+                }
+            }
+        }
+
+        grid.neighbor_counts = (0..cell_count)
+            .map(|cell| {
+                grid.neighbors(cell)
+                    .filter(|&neighbor| grid.is_roll(neighbor))
+                    .count() as u16
+            })
+            .collect();
+
+        grid
+    }
+
+    pub fn topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.neighbor_counts.len()
+    }
+
+    pub fn is_roll(&self, cell: usize) -> bool {
+        self.occupied[cell / 64] & (1 << (cell % 64)) != 0
+    }
+
+    pub fn neighbor_count(&self, cell: usize) -> u16 {
+        self.neighbor_counts[cell]
+    }
+
+    pub fn rolls(&self) -> impl Iterator<Item = usize> + '_ {
+        self.occupied.iter().enumerate().flat_map(|(word_index, &word)| {
+            let mut remaining = word;
+            std::iter::from_fn(move || {
+                if remaining == 0 {
+                    return None;
+                }
+                let bit = remaining.trailing_zeros() as usize;
+                remaining &= remaining - 1;
+                Some(word_index * 64 + bit)
+            })
+        })
+    }
+
+    // Cells inside this cell's neighbourhood, i.e. the ones its count is taken over.
+    pub fn neighbors(&self, cell: usize) -> impl Iterator<Item = usize> + '_ {
+        offset_cells(&self.topology, &self.offsets, cell, 1)
+    }
+
+    // Cells whose neighbourhood contains this cell. Identical to `neighbors` for
+    // symmetric stencils, but custom stencils may be one-sided.
+    pub fn dependents(&self, cell: usize) -> impl Iterator<Item = usize> + '_ {
+        offset_cells(&self.topology, &self.offsets, cell, -1)
+    }
+
+    // Places a roll and increments the cached count of every dependent.
+    pub fn insert(&mut self, cell: usize) {
+        self.occupied[cell / 64] |= 1 << (cell % 64);
+        for dependent in offset_cells(&self.topology, &self.offsets, cell, -1) {
+            self.neighbor_counts[dependent] += 1;
+        }
+    }
+
+    // Clears the roll and decrements the cached count of every dependent.
+    pub fn remove(&mut self, cell: usize) {
+        self.occupied[cell / 64] &= !(1 << (cell % 64));
+        for dependent in offset_cells(&self.topology, &self.offsets, cell, -1) {
+            self.neighbor_counts[dependent] -= 1;
+        }
+    }
+}
+
+fn offset_cells<'a>(
+    topology: &'a Topology,
+    offsets: &'a [[i32; 3]],
+    cell: usize,
+    sign: i32,
+) -> impl Iterator<Item = usize> + 'a {
+    offsets.iter().filter_map(move |&offset| topology.step(cell, offset, sign))
+}
@@ -0,0 +1,327 @@
+use crate::grid::RollGrid;
+use std::collections::{HashMap, VecDeque};
+
+// Keeps both puzzle answers current while rolls are added and deleted.
+//
+// The rolls that survive peeling form the unique largest set in which every
+// member has at least `threshold` neighbours inside the set (the "core"), so
+// the total removable count is simply `rolls - core`. Deleting a roll can only
+// shrink the core, which is a local cascade; adding one can only grow it, and
+// the growth is confined to the non-core rolls connected to the new roll.
+pub struct IncrementalEngine {
+    grid: RollGrid,
+    threshold: u16,
+    roll_count: usize,
+    accessible_count: usize,
+    in_core: Vec<bool>,
+    core_size: usize,
+    // Number of core rolls in each cell's neighbourhood, kept for every cell.
+    core_counts: Vec<u16>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct EditEffect {
+    pub accessible: (usize, usize),
+    pub removable: (usize, usize),
+}
+
+impl IncrementalEngine {
+    pub fn new(grid: &RollGrid, threshold: u16) -> Self {
+        let cell_count = grid.topology().cell_count();
+        let mut engine = IncrementalEngine {
+            grid: grid.clone(),
+            threshold,
+            roll_count: grid.rolls().count(),
+            accessible_count: 0,
+            in_core: vec![false; cell_count],
+            core_size: 0,
+            core_counts: (0..cell_count).map(|cell| grid.neighbor_count(cell)).collect(),
+        };
+
+        // Start from "everything is core" and peel it down.
+        for cell in grid.rolls() {
+            engine.in_core[cell] = true;
+        }
+        engine.core_size = engine.roll_count;
+        engine.accessible_count = grid.rolls().filter(|&cell| engine.is_accessible(cell)).count();
+
+        let pending: Vec<usize> = grid
+            .rolls()
+            .filter(|&cell| engine.core_counts[cell] < threshold)
+            .collect();
+        engine.shrink_core(pending);
+        engine
+    }
+
+    pub fn accessible_count(&self) -> usize {
+        self.accessible_count
+    }
+
+    pub fn total_removable(&self) -> usize {
+        self.roll_count - self.core_size
+    }
+
+    pub fn insert(&mut self, cell: usize) -> EditEffect {
+        let before = self.snapshot();
+        if self.grid.is_roll(cell) {
+            return self.effect_since(before);
+        }
+
+        let affected = self.affected_cells(cell);
+        self.accessible_count -= self.accessible_among(&affected);
+        self.grid.insert(cell);
+        self.roll_count += 1;
+        self.accessible_count += self.accessible_among(&affected);
+
+        self.grow_core(cell);
+        self.effect_since(before)
+    }
+
+    pub fn remove(&mut self, cell: usize) -> EditEffect {
+        let before = self.snapshot();
+        if !self.grid.is_roll(cell) {
+            return self.effect_since(before);
+        }
+
+        let affected = self.affected_cells(cell);
+        self.accessible_count -= self.accessible_among(&affected);
+        self.grid.remove(cell);
+        self.roll_count -= 1;
+        self.accessible_count += self.accessible_among(&affected);
+
+        if self.in_core[cell] {
+            let mut pending = Vec::new();
+            self.leave_core(cell, &mut pending);
+            self.shrink_core(pending);
+        }
+        self.effect_since(before)
+    }
+
+    fn is_accessible(&self, cell: usize) -> bool {
+        self.grid.is_roll(cell) && self.grid.neighbor_count(cell) < self.threshold
+    }
+
+    // The edited cell plus every cell whose count includes it, deduplicated since
+    // wrapped stencils can reach the same cell twice.
+    fn affected_cells(&self, cell: usize) -> Vec<usize> {
+        let mut cells: Vec<usize> = self.grid.dependents(cell).collect();
+        cells.push(cell);
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+
+    fn accessible_among(&self, cells: &[usize]) -> usize {
+        cells.iter().filter(|&&cell| self.is_accessible(cell)).count()
+    }
+
+    // Drops a cell from the core, queueing core members that fall below the
+    // threshold as a result.
+    fn leave_core(&mut self, cell: usize, pending: &mut Vec<usize>) {
+        self.in_core[cell] = false;
+        self.core_size -= 1;
+
+        for dependent in self.grid.dependents(cell) {
+            self.core_counts[dependent] -= 1;
+            if self.in_core[dependent] && self.core_counts[dependent] + 1 == self.threshold {
+                pending.push(dependent);
+            }
+        }
+    }
+
+    fn shrink_core(&mut self, mut pending: Vec<usize>) {
+        while let Some(cell) = pending.pop() {
+            self.leave_core(cell, &mut pending);
+        }
+    }
+
+    fn grow_core(&mut self, inserted: usize) {
+        // Non-core rolls connected to the new roll are the only candidates. Each
+        // starts with its core neighbours plus its candidate neighbours, and the
+        // candidates are peeled against that local count.
+        let mut local_counts: HashMap<usize, u16> = HashMap::new();
+        let mut frontier: VecDeque<usize> = VecDeque::from([inserted]);
+        local_counts.insert(inserted, 0);
+        while let Some(cell) = frontier.pop_front() {
+            for next in self.grid.neighbors(cell).chain(self.grid.dependents(cell)) {
+                if self.grid.is_roll(next) && !self.in_core[next] && !local_counts.contains_key(&next) {
+                    local_counts.insert(next, 0);
+                    frontier.push_back(next);
+                }
+            }
+        }
+
+        let candidates: Vec<usize> = local_counts.keys().copied().collect();
+        for &cell in &candidates {
+            let candidate_neighbors = self
+                .grid
+                .neighbors(cell)
+                .filter(|neighbor| local_counts.contains_key(neighbor))
+                .count() as u16;
+            local_counts.insert(cell, self.core_counts[cell] + candidate_neighbors);
+        }
+
+        let mut pending: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|cell| local_counts[cell] < self.threshold)
+            .collect();
+        while let Some(cell) = pending.pop() {
+            local_counts.remove(&cell);
+            for dependent in self.grid.dependents(cell) {
+                if let Some(count) = local_counts.get_mut(&dependent) {
+                    *count -= 1;
+                    if *count + 1 == self.threshold {
+                        pending.push(dependent);
+                    }
+                }
+            }
+        }
+
+        for cell in local_counts.into_keys() {
+            self.in_core[cell] = true;
+            self.core_size += 1;
+            for dependent in self.grid.dependents(cell) {
+                self.core_counts[dependent] += 1;
+            }
+        }
+    }
+
+    fn snapshot(&self) -> (usize, usize) {
+        (self.accessible_count(), self.total_removable())
+    }
+
+    fn effect_since(&self, before: (usize, usize)) -> EditEffect {
+        EditEffect {
+            accessible: (before.0, self.accessible_count()),
+            removable: (before.1, self.total_removable()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neighbourhood::Neighbourhood;
+    use crate::options::Edit::{self, Add, Delete};
+    use crate::{count_accessible_rolls, count_total_removable};
+
+    const EXAMPLE: &str = "..@@.@@@@.\n@@@.@.@.@@\n@@@@@.@.@@\n@.@@@@..@.\n@@.@@@@.@@\n\
+                           .@@@@@@@.@\n.@.@.@.@@@\n@.@@@.@@@@\n.@@@@@@@@.\n@.@.@@@.@.\n";
+
+    fn render(grid: &RollGrid) -> String {
+        let [layers, rows, cols] = grid.topology().shape;
+        let mut text = String::new();
+        for cell in 0..grid.cell_count() {
+            let [layer, row, col] = grid.topology().coordinates(cell);
+            if layer > 0 && row == 0 && col == 0 {
+                text.push('\n');
+            }
+            text.push(if grid.is_roll(cell) { '@' } else { '.' });
+            if col + 1 == cols {
+                text.push('\n');
+            }
+        }
+        assert_eq!(text.lines().filter(|line| !line.is_empty()).count(), layers * rows);
+        text
+    }
+
+    // Applies the edits in order and after each one compares the engine with
+    // both answers recomputed from a freshly parsed grid. Inputs must span their
+    // full shape so the re-parsed grid has the same topology.
+    fn check_edits(
+        input: &str,
+        neighbourhood: &Neighbourhood,
+        wrap: bool,
+        threshold: u16,
+        edits: &[Edit],
+    ) {
+        let grid = RollGrid::parse(input, neighbourhood, wrap);
+        let mut engine = IncrementalEngine::new(&grid, threshold);
+        for edit in edits {
+            match *edit {
+                Add(position) => engine.insert(grid.topology().cell_at(position).unwrap()),
+                Delete(position) => engine.remove(grid.topology().cell_at(position).unwrap()),
+            };
+            let fresh = RollGrid::parse(&render(&engine.grid), neighbourhood, wrap);
+            assert_eq!(engine.accessible_count(), count_accessible_rolls(&fresh, threshold));
+            assert_eq!(engine.total_removable(), count_total_removable(&fresh, threshold));
+        }
+    }
+
+    #[test]
+    fn example_edits_match_recomputation() {
+        let moore = Neighbourhood::Moore { radius: 1 };
+        let edits = [
+            Delete([0, 4, 4]),
+            Delete([0, 2, 2]),
+            Add([0, 3, 6]),
+            Add([0, 2, 5]),
+            Add([0, 2, 7]),
+            Add([0, 4, 4]),
+            Delete([0, 0, 0]),
+            Add([0, 0, 0]),
+            Add([0, 0, 4]),
+        ];
+        for threshold in [3, 4, 5] {
+            check_edits(EXAMPLE, &moore, false, threshold, &edits);
+        }
+        check_edits(EXAMPLE, &Neighbourhood::VonNeumann { radius: 2 }, false, 6, &edits);
+    }
+
+    // Filling the hole lets the whole ring join the core at once, and emptying
+    // a corner sends it cascading back out.
+    #[test]
+    fn core_grows_and_shrinks_in_one_edit() {
+        let moore = Neighbourhood::Moore { radius: 1 };
+        let edits = [Add([0, 1, 1]), Delete([0, 0, 0]), Add([0, 0, 0]), Delete([0, 1, 1])];
+        check_edits("@@@\n@.@\n@@@\n", &moore, false, 3, &edits);
+        let edits = [Add([0, 1, 1]), Add([0, 2, 2]), Delete([0, 0, 3])];
+        check_edits("@@@@\n@..@\n@..@\n@@@@\n", &moore, false, 3, &edits);
+    }
+
+    // One-sided stencils make a cell's neighbours differ from its dependents.
+    #[test]
+    fn one_sided_stencil_edits() {
+        let one_sided = Neighbourhood::Custom(vec![[0, 0, 1], [0, 1, 0], [0, 1, 1], [0, 2, 0]]);
+        let edits = [
+            Add([0, 1, 1]),
+            Add([0, 0, 3]),
+            Delete([0, 2, 0]),
+            Add([0, 3, 3]),
+            Delete([0, 1, 1]),
+        ];
+        for threshold in 1..=3 {
+            check_edits("@@.@\n@.@@\n@@@.\n.@@@\n", &one_sided, false, threshold, &edits);
+        }
+    }
+
+    // Small wrapped grids reach cells through the stencil more than once.
+    #[test]
+    fn wrapped_edits_match_recomputation() {
+        let moore = Neighbourhood::Moore { radius: 1 };
+        for threshold in 1..=8 {
+            let edits = [
+                Add([0, 1, 1]),
+                Delete([0, 0, 0]),
+                Delete([0, 0, 2]),
+                Add([0, 0, 0]),
+                Add([0, 0, 2]),
+            ];
+            check_edits("@@@\n@.@\n", &moore, true, threshold, &edits);
+            let edits = [Delete([0, 0, 1]), Add([0, 0, 2]), Add([0, 0, 1]), Delete([0, 0, 3])];
+            check_edits("@@.@\n", &moore, true, threshold, &edits);
+        }
+        for threshold in [6, 12, 20, 26] {
+            let edits = [
+                Add([0, 1, 1]),
+                Add([1, 0, 0]),
+                Delete([1, 1, 0]),
+                Delete([0, 0, 0]),
+                Add([1, 1, 0]),
+            ];
+            check_edits("@@\n@.\n\n.@\n@@\n", &moore, true, threshold, &edits);
+            check_edits("@@\n@.\n\n.@\n@@\n", &moore, false, threshold / 3, &edits);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::grid::RollGrid;
+use crate::topology::Topology;
+
+// Result of peeling in synchronous rounds: every roll accessible at the start of
+// a round is removed together, and only then are counts re-examined.
+pub struct PeelLayers {
+    pub removed_per_round: Vec<usize>,
+    // Round (1-based) in which each cell's roll is removed; `None` for empty
+    // cells and for rolls that are never removed.
+    pub removal_round: Vec<Option<u32>>,
+    pub never_removed: Vec<usize>,
+}
+
+pub fn peel_layers(grid: &RollGrid, threshold: u16) -> PeelLayers {
+    let mut grid = grid.clone();
+    let mut removal_round: Vec<Option<u32>> = vec![None; grid.cell_count()];
+    let mut removed_per_round: Vec<usize> = Vec::new();
+
+    let mut current: Vec<usize> = grid
+        .rolls()
+        .filter(|&cell| grid.neighbor_count(cell) < threshold)
+        .collect();
+
+    while !current.is_empty() {
+        let round = removed_per_round.len() as u32 + 1;
+        for &cell in &current {
+            removal_round[cell] = Some(round);
+            grid.remove(cell);
+        }
+        removed_per_round.push(current.len());
+
+        // Only dependents of this round's removals can have changed, and each is
+        // scheduled once thanks to the round marker.
+        let mut next: Vec<usize> = Vec::new();
+        for &cell in &current {
+            for dependent in grid.dependents(cell) {
+                if grid.is_roll(dependent)
+                    && removal_round[dependent].is_none()
+                    && grid.neighbor_count(dependent) < threshold
+                {
+                    removal_round[dependent] = Some(round + 1);
+                    next.push(dependent);
+                }
+            }
+        }
+        current = next;
+    }
+
+    PeelLayers {
+        removed_per_round,
+        removal_round,
+        never_removed: grid.rolls().collect(),
+    }
+}
+
+impl PeelLayers {
+    pub fn print_report(&self, topology: &Topology) {
+        println!("Rounds: {}", self.removed_per_round.len());
+        for (index, removed) in self.removed_per_round.iter().enumerate() {
+            println!("Round {}: {} removed", index + 1, removed);
+        }
+
+        println!("Never removed: {}", self.never_removed.len());
+        for &cell in &self.never_removed {
+            let [layer, row, col] = topology.coordinates(cell);
+            if topology.is_volumetric() {
+                println!("  ({}, {}, {})", layer, row, col);
+            } else {
+                println!("  ({}, {})", row, col);
+            }
+        }
+
+        // Depth map: removal round in base 36 ('+' beyond 35), '@' for rolls that
+        // stay, '.' for empty cells. Layers are separated by a blank line.
+        let mut symbols: Vec<char> = self
+            .removal_round
+            .iter()
+            .map(|round| match round {
+                Some(round) => char::from_digit(*round, 36).unwrap_or('+'),
+                None => '.',
+            })
+            .collect();
+        for &cell in &self.never_removed {
+            symbols[cell] = '@';
+        }
+        let [_, rows, cols] = topology.shape;
+        for (layer, cells) in symbols.chunks(rows * cols).enumerate() {
+            if layer > 0 {
+                println!();
+            }
+            for row in cells.chunks(cols) {
+                println!("{}", row.iter().collect::<String>());
+            }
+        }
+    }
+}
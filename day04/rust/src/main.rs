@@ -1,103 +1,154 @@
-use std::collections::{HashSet, VecDeque};
+mod grid;
+mod incremental;
+mod layers;
+mod neighbourhood;
+mod options;
+mod topology;
+
+use grid::RollGrid;
+use incremental::{EditEffect, IncrementalEngine};
+use layers::peel_layers;
+use options::{Edit, Options};
+use std::env;
 use std::fs;
 
-const DIRECTION_OFFSETS: [(i32, i32); 8] = [
-    (-1, -1), (-1, 0), (-1, 1),
-    (0, -1),           (0, 1),
-    (1, -1),  (1, 0),  (1, 1),
-];
-
 fn main() {
-    let input = fs::read_to_string("../input.txt").expect("Failed to read input file");
-    let grid: Vec<&str> = input.lines().collect();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = Options::from_args(&args);
+    let threshold = options.rule.threshold;
+
+    let input = fs::read_to_string(&options.input_path).expect("Failed to read input file");
+    let grid = RollGrid::parse(&input, &options.rule.neighbourhood, options.wrap);
+
+    println!("Part 1: {}", count_accessible_rolls(&grid, threshold));
+    println!("Part 2: {}", count_total_removable(&grid, threshold));
 
-    println!("Part 1: {}", count_accessible_rolls(&grid));
-    println!("Part 2: {}", count_total_removable(&grid));
+    if options.show_layers {
+        peel_layers(&grid, threshold).print_report(grid.topology());
+    }
+
+    if !options.edits.is_empty() {
+        let mut engine = IncrementalEngine::new(&grid, threshold);
+        for edit in &options.edits {
+            let (label, position) = match edit {
+                Edit::Add(position) => ("Add", position),
+                Edit::Delete(position) => ("Delete", position),
+            };
+            let cell = grid
+                .topology()
+                .cell_at(*position)
+                .unwrap_or_else(|| panic!("Position {:?} is outside the grid", position));
+            let effect = match edit {
+                Edit::Add(_) => engine.insert(cell),
+                Edit::Delete(_) => engine.remove(cell),
+            };
+            let [layer, row, col] = *position;
+            let location = if grid.topology().is_volumetric() {
+                format!("({}, {}, {})", layer, row, col)
+            } else {
+                format!("({}, {})", row, col)
+            };
+            println!("{} {}: {}", label, location, describe_effect(&effect));
+        }
+    }
 }
 
-fn count_neighbors(row: i32, col: i32, height: i32, width: i32, grid: &[Vec<char>]) -> usize {
-    DIRECTION_OFFSETS.iter().filter(|(row_offset, col_offset)| {
-        let neighbor_row = row + row_offset;
-        let neighbor_col = col + col_offset;
-        neighbor_row >= 0
-            && neighbor_row < height
-            && neighbor_col >= 0
-            && neighbor_col < width
-            && grid[neighbor_row as usize][neighbor_col as usize] == '@'
-    }).count()
+fn describe_effect(effect: &EditEffect) -> String {
+    let change = |(before, after): (usize, usize)| {
+        format!("{} -> {} ({:+})", before, after, after as i64 - before as i64)
+    };
+    format!(
+        "accessible {}, removable {}",
+        change(effect.accessible),
+        change(effect.removable)
+    )
 }
 
-fn is_accessible(row: i32, col: i32, height: i32, width: i32, grid: &[Vec<char>]) -> bool {
-    count_neighbors(row, col, height, width, grid) < 4
+fn is_accessible(grid: &RollGrid, cell: usize, threshold: u16) -> bool {
+    grid.neighbor_count(cell) < threshold
 }
 
-fn count_accessible_rolls(grid: &[&str]) -> usize {
-    let height = grid.len() as i32;
-    let width = grid[0].len() as i32;
+fn count_accessible_rolls(grid: &RollGrid, threshold: u16) -> usize {
+    grid.rolls().filter(|&cell| is_accessible(grid, cell, threshold)).count()
+}
 
-    let char_grid: Vec<Vec<char>> = grid.iter().map(|row| row.chars().collect()).collect();
+fn count_total_removable(grid: &RollGrid, threshold: u16) -> usize {
+    let mut grid = grid.clone();
+    let mut queued = vec![false; grid.cell_count()];
+
+    // Seed with all initially accessible rolls
+    let mut pending: Vec<usize> = grid
+        .rolls()
+        .filter(|&cell| is_accessible(&grid, cell, threshold))
+        .collect();
+    for &cell in &pending {
+        queued[cell] = true;
+    }
+    let mut total_removed = 0;
 
-    let mut count = 0;
-    for row in 0..height {
-        for col in 0..width {
-            if char_grid[row as usize][col as usize] == '@'
-                && is_accessible(row, col, height, width, &char_grid)
+    while let Some(cell) = pending.pop() {
+        grid.remove(cell);
+        total_removed += 1;
+
+        // On wrapped grids a stencil can reach the same cell twice, so one
+        // removal may drop a count by more than one; the queued bit keeps each
+        // roll from being queued again.
+        for dependent in grid.dependents(cell) {
+            if grid.is_roll(dependent)
+                && !queued[dependent]
+                && is_accessible(&grid, dependent, threshold)
             {
-                count += 1;
+                queued[dependent] = true;
+                pending.push(dependent);
             }
         }
     }
-    count
-}
 
-fn count_total_removable(grid: &[&str]) -> usize {
-    let height = grid.len() as i32;
-    let width = grid[0].len() as i32;
+    total_removed
+}
 
-    let mut char_grid: Vec<Vec<char>> = grid.iter().map(|row| row.chars().collect()).collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::peel_layers;
+    use crate::neighbourhood::Neighbourhood;
+
+    // Peeling in rounds removes exactly the rolls the queue-based pass does.
+    fn assert_matches_layers(input: &str, wrap: bool, threshold: u16) -> usize {
+        let grid = RollGrid::parse(input, &Neighbourhood::Moore { radius: 1 }, wrap);
+        let removed = count_total_removable(&grid, threshold);
+        let layers = peel_layers(&grid, threshold);
+        assert_eq!(removed, layers.removed_per_round.iter().sum::<usize>());
+        removed
+    }
 
-    let mut queue: VecDeque<(i32, i32)> = VecDeque::new();
-    let mut in_queue: HashSet<(i32, i32)> = HashSet::new();
+    #[test]
+    fn example_grid() {
+        let input = "..@@.@@@@.\n@@@.@.@.@@\n@@@@@.@.@@\n@.@@@@..@.\n@@.@@@@.@@\n\
+                     .@@@@@@@.@\n.@.@.@.@@@\n@.@@@.@@@@\n.@@@@@@@@.\n@.@.@@@.@.\n";
+        let grid = RollGrid::parse(input, &Neighbourhood::Moore { radius: 1 }, false);
+        assert_eq!(count_accessible_rolls(&grid, 4), 13);
+        assert_eq!(assert_matches_layers(input, false, 4), 43);
+    }
 
-    // Seed queue with all initially accessible rolls
-    for row in 0..height {
-        for col in 0..width {
-            if char_grid[row as usize][col as usize] == '@'
-                && is_accessible(row, col, height, width, &char_grid)
-            {
-                queue.push_back((row, col));
-                in_queue.insert((row, col));
-            }
+    // On a height-2 wrapped grid the rows above and below are the same row, so
+    // one removal lowers some counts by two.
+    #[test]
+    fn wrapped_counts_dropping_by_two() {
+        assert_eq!(assert_matches_layers("@@@\n@.@\n", true, 7), 5);
+        for threshold in 1..=9 {
+            assert_matches_layers("@@@\n@.@\n", true, threshold);
+            assert_matches_layers("@@\n@@\n@.\n", true, threshold);
+            assert_matches_layers("@@\n@.\n\n.@\n@@\n", true, threshold * 3);
         }
     }
 
-    let mut total_removed = 0;
-
-    while let Some((row, col)) = queue.pop_front() {
-        in_queue.remove(&(row, col));
-
-        // Check if still a roll and still accessible
-        if char_grid[row as usize][col as usize] == '@'
-            && is_accessible(row, col, height, width, &char_grid)
-        {
-            char_grid[row as usize][col as usize] = '.';
-            total_removed += 1;
-
-            // Check if any neighbors became newly accessible
-            for (row_offset, col_offset) in DIRECTION_OFFSETS {
-                let (neighbor_row, neighbor_col) = (row + row_offset, col + col_offset);
-                if neighbor_row >= 0 && neighbor_row < height && neighbor_col >= 0 && neighbor_col < width {
-                    if char_grid[neighbor_row as usize][neighbor_col as usize] == '@'
-                        && !in_queue.contains(&(neighbor_row, neighbor_col))
-                        && is_accessible(neighbor_row, neighbor_col, height, width, &char_grid)
-                    {
-                        queue.push_back((neighbor_row, neighbor_col));
-                        in_queue.insert((neighbor_row, neighbor_col));
-                    }
-                }
-            }
+    #[test]
+    fn wrapped_single_row() {
+        for threshold in 1..=9 {
+            assert_matches_layers("@@.@@\n", true, threshold);
+            assert_matches_layers("@@@@\n", true, threshold);
         }
+        assert_eq!(assert_matches_layers("@@@@\n", true, 7), 0);
     }
-
-    total_removed
 }
@@ -0,0 +1,74 @@
+#[derive(Clone, Debug)]
+pub enum Neighbourhood {
+    Moore { radius: u32 },
+    VonNeumann { radius: u32 },
+    // (layer, row, col) offsets; 2D stencils use a zero layer offset.
+    Custom(Vec<[i32; 3]>),
+}
+
+impl Neighbourhood {
+    // Relative (layer, row, col) offsets of the stencil, excluding the cell
+    // itself and without duplicates. Moore and von Neumann stencils only extend
+    // across layers for volumetric grids, so a 3D Moore radius 1 stencil is the
+    // 26-neighbourhood. Custom stencils need not be symmetric.
+    pub fn offsets(&self, volumetric: bool) -> Vec<[i32; 3]> {
+        let box_offsets = |r: i32| {
+            let layer_reach = if volumetric { r } else { 0 };
+            (-layer_reach..=layer_reach)
+                .flat_map(move |dz| {
+                    (-r..=r).flat_map(move |dr| (-r..=r).map(move |dc| [dz, dr, dc]))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut offsets: Vec<[i32; 3]> = match self {
+            Neighbourhood::Moore { radius } => box_offsets(*radius as i32),
+            Neighbourhood::VonNeumann { radius } => {
+                let r = *radius as i32;
+                box_offsets(r)
+                    .into_iter()
+                    .filter(|offset| offset.iter().map(|d| d.abs()).sum::<i32>() <= r)
+                    .collect()
+            }
+            Neighbourhood::Custom(offsets) => offsets.clone(),
+        };
+        offsets.retain(|&offset| offset != [0, 0, 0]);
+        offsets.sort_unstable();
+        offsets.dedup();
+        offsets
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AccessRule {
+    pub neighbourhood: Neighbourhood,
+    // A roll is accessible when strictly fewer than this many neighbours are rolls.
+    pub threshold: u16,
+}
+
+impl Default for AccessRule {
+    fn default() -> Self {
+        AccessRule {
+            neighbourhood: Neighbourhood::Moore { radius: 1 },
+            threshold: 4,
+        }
+    }
+}
+
+// Parses `"dr,dc;dr,dc;..."`, or `"dz,dr,dc;..."` for offsets across layers.
+pub fn parse_stencil(spec: &str) -> Vec<[i32; 3]> {
+    spec.split(';')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let parts: Vec<i32> = entry
+                .split(',')
+                .map(|s| s.trim().parse().expect("Invalid stencil offset"))
+                .collect();
+            match parts[..] {
+                [dr, dc] => [0, dr, dc],
+                [dz, dr, dc] => [dz, dr, dc],
+                _ => panic!("Stencil offsets must be row,col or layer,row,col: {}", entry),
+            }
+        })
+        .collect()
+}
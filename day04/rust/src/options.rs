@@ -0,0 +1,75 @@
+use crate::neighbourhood::{parse_stencil, AccessRule, Neighbourhood};
+
+pub enum Edit {
+    Add([usize; 3]),
+    Delete([usize; 3]),
+}
+
+pub struct Options {
+    pub input_path: String,
+    pub rule: AccessRule,
+    pub wrap: bool,
+    pub show_layers: bool,
+    pub edits: Vec<Edit>,
+}
+
+impl Options {
+    // Recognises `--input PATH`, `--neighbourhood moore|von-neumann`, `--radius N`,
+    // `--stencil "..."`, `--threshold N`, `--wrap`, `--layers` and repeated
+    // `--add row,col` / `--delete row,col` edits (`layer,row,col` for volumetric
+    // grids), applied in order. Unspecified options keep the puzzle's defaults.
+    pub fn from_args(args: &[String]) -> Self {
+        let mut input_path = "../input.txt".to_string();
+        let mut rule = AccessRule::default();
+        let mut wrap = false;
+        let mut show_layers = false;
+        let mut edits: Vec<Edit> = Vec::new();
+        let mut radius = 1;
+        let mut kind = "moore".to_string();
+        let mut stencil: Option<Vec<[i32; 3]>> = None;
+
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let mut value = || iter.next().unwrap_or_else(|| panic!("Missing value for {}", flag));
+            match flag.as_str() {
+                "--input" => input_path = value().to_string(),
+                "--neighbourhood" => kind = value().to_string(),
+                "--radius" => radius = value().parse().expect("Invalid radius"),
+                "--threshold" => rule.threshold = value().parse().expect("Invalid threshold"),
+                "--stencil" => stencil = Some(parse_stencil(value())),
+                "--wrap" => wrap = true,
+                "--layers" => show_layers = true,
+                "--add" => edits.push(Edit::Add(parse_position(value()))),
+                "--delete" => edits.push(Edit::Delete(parse_position(value()))),
+                other => panic!("Unknown argument: {}", other),
+            }
+        }
+
+        rule.neighbourhood = match (stencil, kind.as_str()) {
+            (Some(offsets), _) => Neighbourhood::Custom(offsets),
+            (None, "moore") => Neighbourhood::Moore { radius },
+            (None, "von-neumann") => Neighbourhood::VonNeumann { radius },
+            (None, other) => panic!("Unknown neighbourhood: {}", other),
+        };
+
+        Options {
+            input_path,
+            rule,
+            wrap,
+            show_layers,
+            edits,
+        }
+    }
+}
+
+fn parse_position(spec: &str) -> [usize; 3] {
+    let parts: Vec<usize> = spec
+        .split(',')
+        .map(|s| s.trim().parse().expect("Invalid position"))
+        .collect();
+    match parts[..] {
+        [row, col] => [0, row, col],
+        [layer, row, col] => [layer, row, col],
+        _ => panic!("Positions must be row,col or layer,row,col: {}", spec),
+    }
+}
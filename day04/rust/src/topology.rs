@@ -0,0 +1,47 @@
+// Shape of the roll store as (layers, rows, cols). Flat 2D grids are a single
+// layer. With `wrap` set every axis is toroidal, otherwise cells past an edge
+// simply do not exist.
+#[derive(Clone, Copy, Debug)]
+pub struct Topology {
+    pub shape: [usize; 3],
+    pub wrap: bool,
+}
+
+impl Topology {
+    pub fn cell_count(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    pub fn is_volumetric(&self) -> bool {
+        self.shape[0] > 1
+    }
+
+    pub fn coordinates(&self, cell: usize) -> [usize; 3] {
+        let [_, rows, cols] = self.shape;
+        [cell / (rows * cols), (cell / cols) % rows, cell % cols]
+    }
+
+    pub fn cell_at(&self, coordinates: [usize; 3]) -> Option<usize> {
+        let [layers, rows, cols] = self.shape;
+        let [layer, row, col] = coordinates;
+        (layer < layers && row < rows && col < cols).then(|| (layer * rows + row) * cols + col)
+    }
+
+    // Cell reached by moving `sign * offset` from `cell`, or `None` when that
+    // falls off a hard edge.
+    pub fn step(&self, cell: usize, offset: [i32; 3], sign: i32) -> Option<usize> {
+        let coordinates = self.coordinates(cell);
+        let mut index = 0;
+        for axis in 0..3 {
+            let size = self.shape[axis] as i64;
+            let mut value = coordinates[axis] as i64 + (sign * offset[axis]) as i64;
+            if self.wrap {
+                value = value.rem_euclid(size);
+            } else if value < 0 || value >= size {
+                return None;
+            }
+            index = index * size + value;
+        }
+        Some(index as usize)
+    }
+}
@@ -0,0 +1,152 @@
+use crate::bound::Bound;
+use crate::interval_set::IntervalSet;
+use std::thread;
+
+// Membership of every ID, in input order. The IDs are sorted once and swept
+// against the merged ranges, so a batch costs O(m log m + n) rather than a
+// binary search per ID.
+pub fn batch_membership<T: Bound>(set: &IntervalSet<T>, ids: &[T]) -> Vec<bool> {
+    let mut order: Vec<usize> = (0..ids.len()).collect();
+    order.sort_unstable_by_key(|&index| ids[index]);
+
+    let ranges = set.ranges();
+    let mut membership = vec![false; ids.len()];
+    let mut range_index = 0;
+    for index in order {
+        let id = ids[index];
+        while range_index < ranges.len() && ranges[range_index].1 < id {
+            range_index += 1;
+        }
+        membership[index] = ranges
+            .get(range_index)
+            .is_some_and(|&(start, _)| start <= id);
+    }
+    membership
+}
+
+// Splits the batch into one contiguous chunk per thread, each swept independently.
+pub fn parallel_batch_membership<T>(set: &IntervalSet<T>, ids: &[T], threads: usize) -> Vec<bool>
+where
+    T: Bound + Send + Sync,
+{
+    let chunk_size = ids.len().div_ceil(threads.max(1)).max(1);
+    let mut membership = vec![false; ids.len()];
+
+    thread::scope(|scope| {
+        for (chunk, output) in ids.chunks(chunk_size).zip(membership.chunks_mut(chunk_size)) {
+            scope.spawn(move || output.copy_from_slice(&batch_membership(set, chunk)));
+        }
+    });
+    membership
+}
+
+// Answers "how many IDs of the set lie in [start, end]" in O(log n) using
+// prefix sums of the merged range sizes.
+pub struct CoverageIndex<T> {
+    ranges: Vec<(T, T)>,
+    // prefix[i] is the number of IDs in the first i ranges.
+    prefix: Vec<u128>,
+}
+
+impl<T: Bound> CoverageIndex<T> {
+    // `None` when the set's total coverage does not fit in a `u128`.
+    pub fn new(set: &IntervalSet<T>) -> Option<Self> {
+        let mut prefix = vec![0u128];
+        for &(start, end) in set.ranges() {
+            let total = prefix.last().unwrap().checked_add(T::distance(start, end))?.checked_add(1)?;
+            prefix.push(total);
+        }
+        Some(CoverageIndex {
+            ranges: set.ranges().to_vec(),
+            prefix,
+        })
+    }
+
+    pub fn count_in(&self, start: T, end: T) -> u128 {
+        if start > end {
+            return 0;
+        }
+        self.count_before(end) + u128::from(self.contains(end)) - self.count_before(start)
+    }
+
+    // Number of IDs strictly below `value`.
+    fn count_before(&self, value: T) -> u128 {
+        let index = self.ranges.partition_point(|&(_, e)| e < value);
+        match self.ranges.get(index) {
+            Some(&(start, _)) if start < value => self.prefix[index] + T::distance(start, value),
+            _ => self.prefix[index],
+        }
+    }
+
+    fn contains(&self, value: T) -> bool {
+        let index = self.ranges.partition_point(|&(_, e)| e < value);
+        self.ranges.get(index).is_some_and(|&(start, _)| start <= value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set<T: Bound>(ranges: &[(T, T)]) -> IntervalSet<T> {
+        IntervalSet::from_ranges(ranges.iter().copied()).unwrap()
+    }
+
+    #[test]
+    fn membership_matches_contains() {
+        let fresh = set(&[(3i64, 5), (10, 14), (16, 20), (12, 18), (-8, -6)]);
+        // Unsorted, repeated and out-of-range IDs.
+        let ids: Vec<i64> = vec![17, 3, -7, 25, 5, 6, 3, 15, -9, 14, 0, 21, 10, 10, -6];
+        let expected: Vec<bool> = ids.iter().map(|&id| fresh.contains(id)).collect();
+
+        assert_eq!(batch_membership(&fresh, &ids), expected);
+        for threads in [1, 2, 3, 4, 15, 16, 100] {
+            assert_eq!(parallel_batch_membership(&fresh, &ids, threads), expected);
+        }
+        assert!(batch_membership(&IntervalSet::new(), &ids).iter().all(|&is_fresh| !is_fresh));
+    }
+
+    #[test]
+    fn membership_of_empty_batch() {
+        let fresh = set(&[(1u64, 2)]);
+        assert!(batch_membership(&fresh, &[]).is_empty());
+        assert!(parallel_batch_membership(&fresh, &[], 4).is_empty());
+    }
+
+    #[test]
+    fn membership_at_type_extremes() {
+        let fresh = set(&[(u128::MAX - 1, u128::MAX), (0, 0)]);
+        let ids = [u128::MAX, 1, 0, u128::MAX - 2];
+        assert_eq!(parallel_batch_membership(&fresh, &ids, 3), [true, false, true, false]);
+    }
+
+    #[test]
+    fn counts_match_direct_count() {
+        let fresh = set(&[(3i64, 5), (10, 14), (16, 20), (-8, -6)]);
+        let index = CoverageIndex::new(&fresh).unwrap();
+        for start in -10..25 {
+            for end in -10..25 {
+                let direct = (start..=end).filter(|&id| fresh.contains(id)).count() as u128;
+                assert_eq!(index.count_in(start, end), direct, "{}-{}", start, end);
+            }
+        }
+    }
+
+    #[test]
+    fn counts_at_type_extremes() {
+        let index = CoverageIndex::new(&set(&[(i64::MIN, i64::MAX)])).unwrap();
+        assert_eq!(index.count_in(i64::MIN, i64::MAX), 1 << 64);
+        assert_eq!(index.count_in(i64::MIN, -1), 1 << 63);
+        assert_eq!(index.count_in(i64::MAX, i64::MAX), 1);
+
+        let index = CoverageIndex::new(&set(&[(0u64, 0), (u64::MAX - 1, u64::MAX)])).unwrap();
+        assert_eq!(index.count_in(0, u64::MAX), 3);
+        assert_eq!(index.count_in(1, u64::MAX - 2), 0);
+        assert_eq!(index.count_in(u64::MAX, 0), 0);
+
+        // The full 128-bit domain holds 2^128 IDs, one more than a u128 counts.
+        assert!(CoverageIndex::new(&set(&[(0u128, u128::MAX)])).is_none());
+        let index = CoverageIndex::new(&set(&[(1u128, u128::MAX)])).unwrap();
+        assert_eq!(index.count_in(0, u128::MAX), u128::MAX);
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+// Integer types usable as interval endpoints. Neighbouring values are reached
+// with checked arithmetic so ranges touching the type's extremes never wrap.
+pub trait Bound: Copy + Ord + Debug + Display + FromStr {
+    fn successor(self) -> Option<Self>;
+    fn predecessor(self) -> Option<Self>;
+
+    // `end - start` as an unsigned count; requires `start <= end`.
+    fn distance(start: Self, end: Self) -> u128;
+}
+
+macro_rules! impl_bound {
+    ($($value:ty => $unsigned:ty),*) => {$(
+        impl Bound for $value {
+            fn successor(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+
+            fn predecessor(self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+
+            fn distance(start: Self, end: Self) -> u128 {
+                // Two's complement subtraction is exact once reinterpreted as
+                // unsigned, because the true difference is non-negative.
+                end.wrapping_sub(start) as $unsigned as u128
+            }
+        }
+    )*};
+}
+
+impl_bound!(u64 => u64, i64 => u64, u128 => u128, i128 => u128);
+
+// Whether a range ending at `end` overlaps or abuts one starting at `start`.
+pub fn touches<T: Bound>(end: T, start: T) -> bool {
+    end >= start || end.successor() == Some(start)
+}
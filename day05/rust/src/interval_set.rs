@@ -0,0 +1,248 @@
+use crate::bound::{touches, Bound};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RangeError {
+    Malformed(String),
+    Inverted(String),
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RangeError::Malformed(text) => write!(f, "malformed range {:?}", text),
+            RangeError::Inverted(text) => write!(f, "inverted range {} (start exceeds end)", text),
+        }
+    }
+}
+
+impl std::error::Error for RangeError {}
+
+// Parses `start-end`, rejecting ranges whose start exceeds their end. A leading
+// `-` belongs to the start value so negative bounds work for signed types.
+pub fn parse_range<T: Bound>(text: &str) -> Result<(T, T), RangeError> {
+    let malformed = || RangeError::Malformed(text.to_string());
+    let split_at = text
+        .char_indices()
+        .skip(1)
+        .find(|&(_, c)| c == '-')
+        .map(|(index, _)| index)
+        .ok_or_else(malformed)?;
+    let start = text[..split_at].trim().parse().map_err(|_| malformed())?;
+    let end = text[split_at + 1..].trim().parse().map_err(|_| malformed())?;
+    validate(start, end)?;
+    Ok((start, end))
+}
+
+fn validate<T: Bound>(start: T, end: T) -> Result<(), RangeError> {
+    if start > end {
+        return Err(RangeError::Inverted(format!("{}-{}", start, end)));
+    }
+    Ok(())
+}
+
+// Sorts valid ranges and coalesces any that overlap or abut.
+fn merge<T: Bound>(mut ranges: Vec<(T, T)>) -> Vec<(T, T)> {
+    ranges.sort_unstable_by_key(|range| range.0);
+
+    let mut merged: Vec<(T, T)> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if touches(last.1, range.0) => last.1 = last.1.max(range.1),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+// A set of integers stored as sorted, disjoint, non-adjacent inclusive ranges.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    ranges: Vec<(T, T)>,
+}
+
+impl<T: Bound> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet::new()
+    }
+}
+
+impl<T: Bound> IntervalSet<T> {
+    pub fn new() -> Self {
+        IntervalSet { ranges: Vec::new() }
+    }
+
+    pub fn from_ranges<I>(ranges: I) -> Result<Self, RangeError>
+    where
+        I: IntoIterator<Item = (T, T)>,
+    {
+        let ranges: Vec<(T, T)> = ranges.into_iter().collect();
+        for &(start, end) in &ranges {
+            validate(start, end)?;
+        }
+        Ok(IntervalSet { ranges: merge(ranges) })
+    }
+
+    pub fn ranges(&self) -> &[(T, T)] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn insert(&mut self, start: T, end: T) -> Result<(), RangeError> {
+        validate(start, end)?;
+        // Every stored range that overlaps or abuts [start, end] lies in lo..hi.
+        let lo = self.ranges.partition_point(|&(_, e)| !touches(e, start));
+        let hi = self.ranges.partition_point(|&(s, _)| touches(end, s));
+
+        let mut merged = (start, end);
+        if lo < hi {
+            merged.0 = merged.0.min(self.ranges[lo].0);
+            merged.1 = merged.1.max(self.ranges[hi - 1].1);
+        }
+        self.ranges.splice(lo..hi, [merged]);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, start: T, end: T) -> Result<(), RangeError> {
+        validate(start, end)?;
+        let lo = self.ranges.partition_point(|&(_, e)| e < start);
+        let hi = self.ranges.partition_point(|&(s, _)| s <= end);
+        if lo >= hi {
+            return Ok(());
+        }
+
+        let mut remainder: Vec<(T, T)> = Vec::new();
+        let (first_start, _) = self.ranges[lo];
+        let (_, last_end) = self.ranges[hi - 1];
+        // Each comparison guarantees the neighbouring value exists.
+        if first_start < start {
+            remainder.push((first_start, start.predecessor().unwrap()));
+        }
+        if last_end > end {
+            remainder.push((end.successor().unwrap(), last_end));
+        }
+        self.ranges.splice(lo..hi, remainder);
+        Ok(())
+    }
+
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        IntervalSet {
+            ranges: merge(self.ranges.iter().chain(&other.ranges).copied().collect()),
+        }
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut result: Vec<(T, T)> = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a_start, a_end) = self.ranges[i];
+            let (b_start, b_end) = other.ranges[j];
+            let start = a_start.max(b_start);
+            let end = a_end.min(b_end);
+            if start <= end {
+                result.push((start, end));
+            }
+            if a_end < b_end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        IntervalSet { ranges: result }
+    }
+
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        match (self.ranges.first(), self.ranges.last()) {
+            (Some(&(low, _)), Some(&(_, high))) => {
+                self.intersection(&other.complement(low, high))
+            }
+            _ => IntervalSet::new(),
+        }
+    }
+
+    // Values in [low, high] that are not in the set.
+    pub fn complement(&self, low: T, high: T) -> IntervalSet<T> {
+        let mut result: Vec<(T, T)> = Vec::new();
+        let mut next = low;
+
+        for &(start, end) in &self.ranges {
+            if end < next {
+                continue;
+            }
+            if start > high {
+                break;
+            }
+            if start > next {
+                result.push((next, start.predecessor().unwrap()));
+            }
+            // Stopping here also covers `end` being the type's maximum, which
+            // has no successor.
+            if end >= high {
+                return IntervalSet { ranges: result };
+            }
+            next = end.successor().unwrap();
+        }
+
+        if next <= high {
+            result.push((next, high));
+        }
+        IntervalSet { ranges: result }
+    }
+
+    // Number of integers in the set, or `None` when that exceeds `u128::MAX`,
+    // which only a full 128-bit domain does.
+    pub fn coverage(&self) -> Option<u128> {
+        self.ranges.iter().try_fold(0u128, |total, &(start, end)| {
+            total.checked_add(T::distance(start, end))?.checked_add(1)
+        })
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.covering_range(value).is_some()
+    }
+
+    // True when every value in [start, end] is in the set.
+    pub fn contains_range(&self, start: T, end: T) -> bool {
+        self.covering_range(start).is_some_and(|(_, range_end)| range_end >= end)
+    }
+
+    // True when at least one value in [start, end] is in the set.
+    pub fn intersects_range(&self, start: T, end: T) -> bool {
+        let index = self.ranges.partition_point(|&(_, e)| e < start);
+        self.ranges.get(index).is_some_and(|&(range_start, _)| range_start <= end)
+    }
+
+    fn covering_range(&self, value: T) -> Option<(T, T)> {
+        let index = self.ranges.partition_point(|&(_, e)| e < value);
+        self.ranges
+            .get(index)
+            .copied()
+            .filter(|&(range_start, _)| range_start <= value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_bounds() {
+        assert_eq!(parse_range::<i64>("-10--5"), Ok((-10, -5)));
+        assert_eq!(parse_range::<i64>("-3-2"), Ok((-3, 2)));
+        assert_eq!(
+            parse_range::<i64>("2--3"),
+            Err(RangeError::Inverted("2--3".to_string()))
+        );
+        assert!(parse_range::<u64>("-3-2").is_err());
+
+        let set = IntervalSet::from_ranges([(-10i64, -5), (-3, 2), (-4, -4), (4, 6)]).unwrap();
+        assert_eq!(set.ranges(), &[(-10, 2), (4, 6)]);
+        assert_eq!(set.coverage(), Some(16));
+        assert!(set.contains(-4) && !set.contains(3));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::bound::Bound;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceRange<T> {
+    pub start: T,
+    pub end: T,
+    // 1-based line in the input the range was read from.
+    pub line: usize,
+}
+
+// Static interval tree over the original, unmerged ranges. Entries are sorted by
+// start and viewed as an implicit balanced tree (the midpoint of each slice is
+// its root), with every subtree annotated by the largest end it contains.
+pub struct IntervalTree<T> {
+    by_start: Vec<SourceRange<T>>,
+    max_end: Vec<T>,
+    by_end: Vec<SourceRange<T>>,
+}
+
+impl<T: Bound> IntervalTree<T> {
+    pub fn new(mut ranges: Vec<SourceRange<T>>) -> Self {
+        ranges.sort_unstable_by_key(|range| (range.start, range.line));
+        let mut by_end = ranges.clone();
+        by_end.sort_unstable_by_key(|range| (range.end, range.line));
+
+        let mut tree = IntervalTree {
+            max_end: ranges.iter().map(|range| range.end).collect(),
+            by_start: ranges,
+            by_end,
+        };
+        tree.annotate(0, tree.by_start.len());
+        tree
+    }
+
+    // Every source range containing `value`, ordered by line.
+    pub fn covering(&self, value: T) -> Vec<SourceRange<T>> {
+        let mut found = Vec::new();
+        self.stab(0, self.by_start.len(), value, &mut found);
+        found.sort_unstable_by_key(|range| range.line);
+        found
+    }
+
+    // The range ending closest below `value`, preferring the earliest line on ties.
+    pub fn nearest_below(&self, value: T) -> Option<SourceRange<T>> {
+        let count = self.by_end.partition_point(|range| range.end < value);
+        let end = self.by_end.get(count.checked_sub(1)?)?.end;
+        let first = self.by_end.partition_point(|range| range.end < end);
+        Some(self.by_end[first])
+    }
+
+    // The range starting closest above `value`, preferring the earliest line on ties.
+    pub fn nearest_above(&self, value: T) -> Option<SourceRange<T>> {
+        let index = self.by_start.partition_point(|range| range.start <= value);
+        self.by_start.get(index).copied()
+    }
+
+    fn annotate(&mut self, lo: usize, hi: usize) -> Option<T> {
+        if lo >= hi {
+            return None;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let left = self.annotate(lo, mid);
+        let right = self.annotate(mid + 1, hi);
+        let subtree_max = [left, right]
+            .into_iter()
+            .flatten()
+            .fold(self.by_start[mid].end, T::max);
+        self.max_end[mid] = subtree_max;
+        Some(subtree_max)
+    }
+
+    fn stab(&self, lo: usize, hi: usize, value: T, found: &mut Vec<SourceRange<T>>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if self.max_end[mid] < value {
+            return;
+        }
+
+        self.stab(lo, mid, value, found);
+        let range = self.by_start[mid];
+        if range.start <= value {
+            if value <= range.end {
+                found.push(range);
+            }
+            self.stab(mid + 1, hi, value, found);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources<T: Bound>(ranges: &[(T, T)]) -> Vec<SourceRange<T>> {
+        ranges
+            .iter()
+            .enumerate()
+            .map(|(index, &(start, end))| SourceRange { start, end, line: index + 1 })
+            .collect()
+    }
+
+    // Checks every query against a scan over the source ranges in line order.
+    fn check<T: Bound>(ranges: &[(T, T)], values: impl IntoIterator<Item = T>) {
+        let sources = sources(ranges);
+        let tree = IntervalTree::new(sources.clone());
+        for value in values {
+            let covering: Vec<SourceRange<T>> = sources
+                .iter()
+                .copied()
+                .filter(|range| range.start <= value && value <= range.end)
+                .collect();
+            assert_eq!(tree.covering(value), covering, "covering {}", value);
+
+            // `min_by_key` keeps the first of equal keys, i.e. the earliest line.
+            let below = sources
+                .iter()
+                .copied()
+                .filter(|range| range.end < value)
+                .min_by_key(|range| std::cmp::Reverse(range.end));
+            assert_eq!(tree.nearest_below(value), below, "below {}", value);
+            let above = sources
+                .iter()
+                .copied()
+                .filter(|range| range.start > value)
+                .min_by_key(|range| range.start);
+            assert_eq!(tree.nearest_above(value), above, "above {}", value);
+        }
+    }
+
+    #[test]
+    fn queries_match_scan() {
+        // Nested, overlapping, repeated and single-value ranges, with ties on
+        // both starts and ends.
+        let ranges: [(i64, i64); 11] = [
+            (3, 5),
+            (10, 14),
+            (16, 20),
+            (12, 18),
+            (12, 14),
+            (3, 5),
+            (30, 30),
+            (1, 40),
+            (22, 25),
+            (26, 29),
+            (22, 22),
+        ];
+        check(&ranges, -2..45);
+        check(&ranges[..7], -2..45);
+        check(&ranges[..1], 0..8);
+        check::<i64>(&[], [0]);
+    }
+
+    #[test]
+    fn queries_at_type_extremes() {
+        let ranges = [(i64::MIN, -1), (i64::MIN, i64::MIN), (0, i64::MAX), (5, i64::MAX)];
+        check(&ranges, [i64::MIN, i64::MIN + 1, -1, 0, 4, 5, i64::MAX]);
+        let ranges = [(0, 0), (u64::MAX, u64::MAX), (1, u64::MAX - 1)];
+        check(&ranges, [0, 1, 2, u64::MAX - 1, u64::MAX]);
+    }
+}
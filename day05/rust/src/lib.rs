@@ -0,0 +1,5 @@
+pub mod batch;
+pub mod bound;
+pub mod interval_set;
+pub mod interval_tree;
+pub mod service;
@@ -1,65 +1,121 @@
+mod options;
+
+use day05::batch::{batch_membership, parallel_batch_membership, CoverageIndex};
+use day05::interval_set::{parse_range, IntervalSet};
+use day05::interval_tree::{IntervalTree, SourceRange};
+use day05::service::FreshnessService;
+use options::Options;
+use std::env;
 use std::fs;
+use std::io::{self, BufReader};
+
+// Ingredient IDs are signed, so ranges may reach below zero.
+type Id = i64;
 
 fn main() {
-    let input = fs::read_to_string("../input.txt").expect("Failed to read input file");
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = Options::from_args(&args);
+
+    let input = fs::read_to_string(&options.input_path).expect("Failed to read input file");
     let lines: Vec<&str> = input.lines().collect();
 
     let blank_index = lines.iter().position(|l| l.is_empty()).unwrap_or(lines.len());
 
-    let mut ranges: Vec<(i64, i64)> = lines[..blank_index]
+    let ranges: Vec<(Id, Id)> = lines[..blank_index]
         .iter()
-        .map(|line| {
-            let parts: Vec<i64> = line.split('-').map(|s| s.parse().unwrap()).collect();
-            (parts[0], parts[1])
+        .enumerate()
+        .map(|(index, line)| {
+            parse_range(line).unwrap_or_else(|err| panic!("Line {}: {}", index + 1, err))
         })
         .collect();
 
-    let ingredients: Vec<i64> = lines[blank_index + 1..]
+    let ingredients: Vec<Id> = lines[blank_index + 1..]
         .iter()
         .filter(|l| !l.is_empty())
         .map(|l| l.parse().unwrap())
         .collect();
 
-    let merged_ranges = merge_ranges(&mut ranges);
-    let fresh_count = ingredients.iter().filter(|&&id| is_in_range(id, &merged_ranges)).count();
+    let fresh = IntervalSet::from_ranges(ranges.iter().copied()).unwrap_or_else(|err| panic!("{}", err));
+
+    // Service modes start from the puzzle's fresh ranges and answer requests
+    // instead of printing the puzzle answers.
+    if options.serve_stdin {
+        let mut service = FreshnessService::new(fresh);
+        service.serve(io::stdin().lock(), io::stdout().lock()).expect("Failed to serve stdin");
+        return;
+    }
+    if let Some(path) = &options.socket_path {
+        serve_socket(path, FreshnessService::new(fresh));
+        return;
+    }
+
+    let membership = if options.threads > 1 {
+        parallel_batch_membership(&fresh, &ingredients, options.threads)
+    } else {
+        batch_membership(&fresh, &ingredients)
+    };
+    let fresh_count = membership.iter().filter(|&&is_fresh| is_fresh).count();
 
     println!("Part 1: {}", fresh_count);
+    println!("Part 2: {}", fresh.coverage().unwrap());
 
-    let total_fresh_ids: i64 = merged_ranges.iter().map(|(start, end)| end - start + 1).sum();
-    println!("Part 2: {}", total_fresh_ids);
+    if !options.count_ranges.is_empty() {
+        let index = CoverageIndex::new(&fresh).unwrap();
+        for &(start, end) in &options.count_ranges {
+            println!("Fresh IDs in {}-{}: {}", start, end, index.count_in(start, end));
+        }
+    }
+
+    if options.provenance {
+        let sources: Vec<SourceRange<Id>> = ranges
+            .iter()
+            .enumerate()
+            .map(|(index, &(start, end))| SourceRange { start, end, line: index + 1 })
+            .collect();
+        print_provenance(&IntervalTree::new(sources), &ingredients);
+    }
 }
 
-fn merge_ranges(ranges: &mut [(i64, i64)]) -> Vec<(i64, i64)> {
-    ranges.sort_by_key(|r| r.0);
-    let mut result: Vec<(i64, i64)> = Vec::new();
+// Clients are served one at a time against the same shared state.
+#[cfg(unix)]
+fn serve_socket(path: &str, mut service: FreshnessService<Id>) {
+    use std::os::unix::net::UnixListener;
 
-    for &range in ranges.iter() {
-        if result.is_empty() || result.last().unwrap().1 < range.0 - 1 {
-            result.push(range);
-        } else {
-            let last = result.last_mut().unwrap();
-            last.1 = last.1.max(range.1);
+    let listener = UnixListener::bind(path).expect("Failed to bind socket");
+    for stream in listener.incoming() {
+        let stream = stream.expect("Failed to accept connection");
+        let reader = BufReader::new(stream.try_clone().expect("Failed to clone socket"));
+        if let Err(err) = service.serve(reader, stream) {
+            eprintln!("Connection closed: {}", err);
         }
     }
+}
 
-    result
+#[cfg(not(unix))]
+fn serve_socket(_path: &str, _service: FreshnessService<Id>) {
+    panic!("--socket requires a platform with Unix domain sockets");
 }
 
-fn is_in_range(id: i64, ranges: &[(i64, i64)]) -> bool {
-    let mut low = 0;
-    let mut high = ranges.len() as i64 - 1;
-
-    while low <= high {
-        let mid = (low + high) / 2;
-        let (start, end) = ranges[mid as usize];
-        if id < start {
-            high = mid - 1;
-        } else if id > end {
-            low = mid + 1;
+fn describe(range: Option<SourceRange<Id>>) -> String {
+    match range {
+        Some(range) => format!("{}-{} (line {})", range.start, range.end, range.line),
+        None => "none".to_string(),
+    }
+}
+
+fn print_provenance(tree: &IntervalTree<Id>, ingredients: &[Id]) {
+    for &id in ingredients {
+        let covering = tree.covering(id);
+        if covering.is_empty() {
+            println!(
+                "{}: spoiled; nearest below {}, nearest above {}",
+                id,
+                describe(tree.nearest_below(id)),
+                describe(tree.nearest_above(id))
+            );
         } else {
-            return true;
+            let lines: Vec<String> = covering.iter().map(|range| range.line.to_string()).collect();
+            println!("{}: fresh via lines {}", id, lines.join(", "));
         }
     }
-
-    false
 }
@@ -0,0 +1,44 @@
+use crate::Id;
+use day05::interval_set::parse_range;
+
+pub struct Options {
+    pub input_path: String,
+    pub provenance: bool,
+    pub serve_stdin: bool,
+    pub socket_path: Option<String>,
+    pub threads: usize,
+    pub count_ranges: Vec<(Id, Id)>,
+}
+
+impl Options {
+    // Recognises `--input PATH`, `--provenance`, `--serve`, `--socket PATH`,
+    // `--threads N` and repeated `--count-range START-END`. Unspecified options
+    // keep the puzzle's defaults.
+    pub fn from_args(args: &[String]) -> Self {
+        let mut options = Options {
+            input_path: "../input.txt".to_string(),
+            provenance: false,
+            serve_stdin: false,
+            socket_path: None,
+            threads: 1,
+            count_ranges: Vec::new(),
+        };
+
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let mut value = || iter.next().unwrap_or_else(|| panic!("Missing value for {}", flag));
+            match flag.as_str() {
+                "--input" => options.input_path = value().to_string(),
+                "--provenance" => options.provenance = true,
+                "--serve" => options.serve_stdin = true,
+                "--socket" => options.socket_path = Some(value().to_string()),
+                "--threads" => options.threads = value().parse().expect("Invalid thread count"),
+                "--count-range" => options.count_ranges.push(
+                    parse_range(value()).unwrap_or_else(|err| panic!("--count-range: {}", err)),
+                ),
+                other => panic!("Unknown argument: {}", other),
+            }
+        }
+        options
+    }
+}
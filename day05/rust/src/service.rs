@@ -0,0 +1,103 @@
+use crate::bound::Bound;
+use crate::interval_set::{parse_range, IntervalSet};
+use std::io::{self, BufRead, Write};
+
+// Line-oriented freshness protocol. Each request gets exactly one reply line:
+//
+//   add START-END     -> ok              IDs in the range become fresh
+//   remove START-END  -> ok              IDs in the range stop being fresh
+//   query ID          -> fresh|spoiled
+//   count             -> number of fresh IDs
+//
+// Malformed requests are answered with `error: ...` and leave the state as is.
+pub struct FreshnessService<T> {
+    fresh: IntervalSet<T>,
+}
+
+impl<T: Bound> FreshnessService<T> {
+    pub fn new(fresh: IntervalSet<T>) -> Self {
+        FreshnessService { fresh }
+    }
+
+    pub fn handle(&mut self, request: &str) -> String {
+        match self.try_handle(request) {
+            Ok(reply) => reply,
+            Err(message) => format!("error: {}", message),
+        }
+    }
+
+    // Answers requests until the reader is exhausted, flushing after every
+    // reply so interactive clients see it immediately.
+    pub fn serve<R: BufRead, W: Write>(&mut self, reader: R, mut writer: W) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            writeln!(writer, "{}", self.handle(&line))?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn try_handle(&mut self, request: &str) -> Result<String, String> {
+        let mut parts = request.split_whitespace();
+        let command = parts.next().unwrap_or("");
+        let argument = parts.next();
+        if parts.next().is_some() {
+            return Err(format!("too many arguments in {:?}", request.trim()));
+        }
+
+        match (command, argument) {
+            ("add", Some(range)) => {
+                let (start, end) = parse_range(range).map_err(|err| err.to_string())?;
+                self.fresh.insert(start, end).map_err(|err| err.to_string())?;
+                Ok("ok".to_string())
+            }
+            ("remove", Some(range)) => {
+                let (start, end) = parse_range(range).map_err(|err| err.to_string())?;
+                self.fresh.remove(start, end).map_err(|err| err.to_string())?;
+                Ok("ok".to_string())
+            }
+            ("query", Some(id)) => {
+                let id: T = id.parse().map_err(|_| format!("invalid ingredient ID {:?}", id))?;
+                Ok(if self.fresh.contains(id) { "fresh" } else { "spoiled" }.to_string())
+            }
+            ("count", None) => Ok(self.fresh.coverage().unwrap().to_string()),
+            _ => Err(format!("unrecognised request {:?}", request.trim())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(requests: &str) -> String {
+        let fresh = IntervalSet::from_ranges([(3i64, 5), (10, 14)]).unwrap();
+        let mut service = FreshnessService::new(fresh);
+        let mut output: Vec<u8> = Vec::new();
+        service.serve(requests.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn requests_and_replies() {
+        let requests = "count\nquery 4\nquery 6\n\nadd 6-9\nquery 6\ncount\n   \n\
+                        remove 4-11\nquery 4\nquery 12\ncount\nadd -5--3\nquery -4\ncount\n";
+        let replies = "8\nfresh\nspoiled\nok\nfresh\n12\nok\nspoiled\nfresh\n4\nok\nfresh\n7\n";
+        assert_eq!(session(requests), replies);
+    }
+
+    // Each bad request gets one `error:` line and changes nothing.
+    #[test]
+    fn errors_leave_state_unchanged() {
+        let requests = "add 9-6\nremove x\nquery\nquery abc\ncount 1\nadd 1-2 3\nfrobnicate\ncount\nquery 4\n";
+        let replies = session(requests);
+        let lines: Vec<&str> = replies.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert!(lines[..7].iter().all(|line| line.starts_with("error: ")), "{:?}", lines);
+        assert_eq!(lines[0], "error: inverted range 9-6 (start exceeds end)");
+        assert_eq!(lines[7..], ["8", "fresh"]);
+    }
+}
@@ -1,120 +1,248 @@
+mod options;
+mod orientation;
+mod problem;
+mod render;
+mod worksheet;
+
 use num_bigint::BigInt;
+use num_rational::BigRational;
+use options::Options;
+use orientation::{Axis, OperatorPosition, Orientation};
+use problem::{evaluate_problem, evaluate_problem_mod, is_operator_symbol, EvalError, Problem};
+use render::render;
+use std::env;
 use std::fs;
-
-struct Problem {
-    numbers: Vec<BigInt>,
-    operation: char,
-}
+use std::ops::Range;
+use std::process;
+use worksheet::Worksheet;
 
 fn main() {
-    let input = fs::read_to_string("../input.txt").expect("Failed to read input file");
-    let lines: Vec<&str> = input.lines().filter(|l| !l.is_empty()).collect();
-
-    let max_width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
-    let padded_lines: Vec<String> = lines
-        .iter()
-        .map(|l| format!("{:width$}", l, width = max_width))
-        .collect();
-    let padded_refs: Vec<&str> = padded_lines.iter().map(|s| s.as_str()).collect();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = Options::from_args(&args);
+
+    let input = fs::read_to_string(&options.input_path).expect("Failed to read input file");
+    let sheet = Worksheet::parse(&input);
+
+    // Re-lays the problems, as read with the active orientation, in the puzzle's
+    // row-wise or column-wise format.
+    if let Some(target) = &options.render {
+        let problems = extract_problems(&sheet, &options.orientation.unwrap_or(Orientation::ROW_WISE));
+        print!("{}", render(&problems, target));
+        return;
+    }
 
-    let problems_part1 = extract_problems(&padded_refs, parse_row_wise);
-    let grand_total_part1: BigInt = problems_part1.iter().map(evaluate_problem).sum();
-    println!("Part 1: {}", grand_total_part1);
+    if let Some(orientation) = &options.orientation {
+        report("Total", &extract_problems(&sheet, orientation), &options);
+        return;
+    }
 
-    let problems_part2 = extract_problems(&padded_refs, parse_column_wise);
-    let grand_total_part2: BigInt = problems_part2.iter().map(evaluate_problem).sum();
-    println!("Part 2: {}", grand_total_part2);
+    report("Part 1", &extract_problems(&sheet, &Orientation::ROW_WISE), &options);
+    report("Part 2", &extract_problems(&sheet, &Orientation::COLUMN_WISE), &options);
 }
 
-fn is_blank_column(lines: &[&str], col: usize) -> bool {
-    lines.iter().all(|line| {
-        col >= line.len() || line.chars().nth(col).unwrap_or(' ') == ' '
-    })
+fn fail(err: &EvalError) -> ! {
+    eprintln!("Error: {}", err);
+    process::exit(1);
 }
 
-fn extract_problems<F>(lines: &[&str], parser: F) -> Vec<Problem>
-where
-    F: Fn(&[&str], usize, usize) -> Problem,
-{
-    let width = lines[0].len();
-    let mut column_ranges: Vec<(usize, usize)> = Vec::new();
-    let mut block_start: Option<usize> = None;
-
-    for col in 0..width {
-        let is_blank = is_blank_column(lines, col);
-        match (block_start, is_blank) {
-            (None, false) => block_start = Some(col),
-            (Some(start), true) => {
-                column_ranges.push((start, col));
-                block_start = None;
-            }
-            _ => {}
+// Prints the grand total, preceded by one line per problem when a breakdown is
+// requested. With a modulus every result and the total are reduced modulo it.
+fn report(label: &str, problems: &[Problem], options: &Options) {
+    let (results, total): (Vec<String>, String) = match &options.modulus {
+        Some(modulus) => {
+            let results: Vec<BigInt> = problems
+                .iter()
+                .map(|problem| evaluate_problem_mod(problem, modulus))
+                .collect::<Result<_, _>>()
+                .unwrap_or_else(|err| fail(&err));
+            let total = results.iter().sum::<BigInt>() % modulus;
+            (
+                results.iter().map(BigInt::to_string).collect(),
+                format!("{} (mod {})", total, modulus),
+            )
+        }
+        None => {
+            let results: Vec<BigRational> = problems
+                .iter()
+                .map(evaluate_problem)
+                .collect::<Result<_, _>>()
+                .unwrap_or_else(|err| fail(&err));
+            let total: BigRational = results.iter().sum();
+            (results.iter().map(BigRational::to_string).collect(), total.to_string())
+        }
+    };
+
+    if options.breakdown {
+        println!("{}:", label);
+        for (problem, result) in problems.iter().zip(&results) {
+            let operands: Vec<String> = problem.numbers.iter().map(|n| n.to_string()).collect();
+            println!(
+                "  columns {}..{} ({}): {} = {}",
+                problem.columns.0,
+                problem.columns.1,
+                problem.operation,
+                operands.join(", "),
+                result
+            );
         }
     }
+    println!("{}: {}", label, total);
+}
 
-    if let Some(start) = block_start {
-        column_ranges.push((start, width));
+// Left-hand operators may sit in their own column, split from the numbers by a
+// blank column, so for that layout a block without digits joins the next one.
+fn extract_problems(sheet: &Worksheet, orientation: &Orientation) -> Vec<Problem> {
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    let mut pending_start: Option<usize> = None;
+
+    for &(start, end) in sheet.blocks() {
+        let start = pending_start.take().unwrap_or(start);
+        let has_digits = (start..end).any(|col| sheet.column(col).iter().any(u8::is_ascii_digit));
+        if orientation.operator == OperatorPosition::Left && !has_digits {
+            pending_start = Some(start);
+        } else {
+            spans.push((start, end));
+        }
+    }
+    if let (Some(start), Some(&(_, end))) = (pending_start, sheet.blocks().last()) {
+        spans.push((start, end));
     }
 
-    column_ranges
+    spans
         .iter()
-        .map(|&(start, end)| parser(lines, start, end))
+        .map(|&(start, end)| parse_problem(sheet, start, end, orientation))
         .collect()
 }
 
-fn parse_row_wise(lines: &[&str], start_col: usize, end_col: usize) -> Problem {
-    let mut numbers: Vec<BigInt> = Vec::new();
-    let mut operation = '+';
+fn find_operator(cells: &[u8]) -> Option<char> {
+    cells
+        .iter()
+        .map(|&byte| byte as char)
+        .find(|&c| is_operator_symbol(c))
+}
 
-    for line in lines {
-        let end = end_col.min(line.len());
-        if start_col >= end {
-            continue;
-        }
-        let row_slice = &line[start_col..end];
-        let digits: String = row_slice.chars().filter(|c| c.is_ascii_digit()).collect();
-        let found_op = row_slice.chars().find(|&c| c == '+' || c == '*');
+// The digits of a row or column slice, taken in the given direction.
+fn read_number(cells: &[u8], forward: bool) -> Option<BigInt> {
+    let mut digits: Vec<u8> = cells.iter().copied().filter(u8::is_ascii_digit).collect();
+    if !forward {
+        digits.reverse();
+    }
+    (!digits.is_empty()).then(|| BigInt::parse_bytes(&digits, 10).unwrap())
+}
+
+fn ordered(range: Range<usize>, forward: bool) -> Vec<usize> {
+    if forward {
+        range.collect()
+    } else {
+        range.rev().collect()
+    }
+}
 
-        if !digits.is_empty() {
-            numbers.push(digits.parse().unwrap());
+fn parse_problem(
+    sheet: &Worksheet,
+    start_col: usize,
+    end_col: usize,
+    orientation: &Orientation,
+) -> Problem {
+    let height = sheet.height();
+    let block = start_col..end_col;
+
+    // Split the block into the operator's row or column and the number cells.
+    let (rows, cols, operator) = match orientation.operator {
+        OperatorPosition::Top => (1..height, block.clone(), find_operator(&sheet.row(0)[block])),
+        OperatorPosition::Bottom => {
+            let operator = find_operator(&sheet.row(height - 1)[block.clone()]);
+            (0..height - 1, block, operator)
         }
-        if let Some(op) = found_op {
-            operation = op;
+        OperatorPosition::Left => {
+            (0..height, start_col + 1..end_col, find_operator(sheet.column(start_col)))
         }
+    };
+
+    let numbers: Vec<BigInt> = match orientation.numbers_along {
+        Axis::Rows => ordered(rows, orientation.top_down)
+            .into_iter()
+            .filter_map(|row| read_number(&sheet.row(row)[cols.clone()], orientation.left_to_right))
+            .collect(),
+        Axis::Columns => ordered(cols, orientation.left_to_right)
+            .into_iter()
+            .filter_map(|col| read_number(&sheet.column(col)[rows.clone()], orientation.top_down))
+            .collect(),
+    };
+
+    // Operands are kept in reading order.
+    Problem {
+        numbers,
+        operation: operator.unwrap_or('+'),
+        columns: (start_col, end_col),
     }
-
-    Problem { numbers, operation }
 }
 
-fn parse_column_wise(lines: &[&str], start_col: usize, end_col: usize) -> Problem {
-    let mut numbers: Vec<BigInt> = Vec::new();
-    let mut operation = '+';
-
-    for col in (start_col..end_col).rev() {
-        let column_chars: Vec<char> = lines
-            .iter()
-            .map(|line| line.chars().nth(col).unwrap_or(' '))
-            .collect();
-
-        let digits: String = column_chars.iter().filter(|c| c.is_ascii_digit()).collect();
-        let found_op = column_chars.iter().find(|&&c| c == '+' || c == '*');
-
-        if !digits.is_empty() {
-            numbers.push(digits.parse().unwrap());
-        }
-        if let Some(&op) = found_op {
-            operation = op;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each worksheet is a list of problems, given as operands and operator.
+    // They mix single digits with long numbers, zeros and every operator.
+    const WORKSHEETS: &[&[(&[&str], char)]] = &[
+        &[(&["7"], '+')],
+        &[(&["123", "45", "6"], '*'), (&["328", "64", "98"], '+')],
+        &[(&["0", "10", "100"], '-'), (&["5"], '/')],
+        &[(&["123456789012", "9"], '^'), (&["1", "22", "333", "4444", "55555"], '%')],
+        &[
+            (&["64", "23", "314"], '+'),
+            (&["6"], '*'),
+            (&["98", "215", "314"], '/'),
+            (&["10", "2"], '^'),
+            (&["9000", "8", "70"], '-'),
+            (&["4", "40"], '%'),
+        ],
+    ];
+
+    // For every worksheet and every orientation, parsing the rendered
+    // worksheet must give back the same operands and operators.
+    #[test]
+    fn render_parse_roundtrip() {
+        for worksheet in WORKSHEETS {
+            let problems: Vec<Problem> = worksheet
+                .iter()
+                .map(|&(numbers, operation)| Problem {
+                    numbers: numbers.iter().map(|n| n.parse().unwrap()).collect(),
+                    operation,
+                    columns: (0, 0),
+                })
+                .collect();
+            for orientation in all_orientations() {
+                let rendered = render(&problems, &orientation);
+                let parsed = extract_problems(&Worksheet::parse(&rendered), &orientation);
+
+                let expected: Vec<(&[BigInt], char)> = problems.iter().map(summary).collect();
+                let actual: Vec<(&[BigInt], char)> = parsed.iter().map(summary).collect();
+                assert_eq!(expected, actual, "{:?} did not round-trip:\n{}", orientation, rendered);
+            }
         }
     }
 
-    numbers.reverse();
-    Problem { numbers, operation }
-}
+    fn summary(problem: &Problem) -> (&[BigInt], char) {
+        (&problem.numbers, problem.operation)
+    }
 
-fn evaluate_problem(problem: &Problem) -> BigInt {
-    match problem.operation {
-        '+' => problem.numbers.iter().cloned().sum(),
-        '*' => problem.numbers.iter().cloned().product(),
-        _ => BigInt::from(0),
+    fn all_orientations() -> Vec<Orientation> {
+        let mut orientations = Vec::new();
+        for numbers_along in [Axis::Rows, Axis::Columns] {
+            for left_to_right in [true, false] {
+                for top_down in [true, false] {
+                    for operator in [OperatorPosition::Top, OperatorPosition::Bottom, OperatorPosition::Left] {
+                        orientations.push(Orientation {
+                            numbers_along,
+                            left_to_right,
+                            top_down,
+                            operator,
+                        });
+                    }
+                }
+            }
+        }
+        orientations
     }
 }
@@ -0,0 +1,93 @@
+use crate::orientation::{OperatorPosition, Orientation};
+use num_bigint::BigInt;
+
+pub struct Options {
+    pub input_path: String,
+    // `None` reads Part 1 row-wise and Part 2 column-wise, as the puzzle does.
+    pub orientation: Option<Orientation>,
+    // Where `--render` re-lays the problems, if requested.
+    pub render: Option<Orientation>,
+    pub modulus: Option<BigInt>,
+    pub breakdown: bool,
+}
+
+impl Options {
+    // Recognises `--input PATH`, `--render rows|columns`, `--modulus M`,
+    // `--breakdown` and the orientation flags `--reading rows|columns`,
+    // `--columns ltr|rtl`, `--rows top-down|bottom-up` and
+    // `--operator top|bottom|left`. A custom orientation starts from the
+    // puzzle's reading for the chosen axis, whatever order the flags come in.
+    pub fn from_args(args: &[String]) -> Self {
+        let mut options = Options {
+            input_path: "../input.txt".to_string(),
+            orientation: None,
+            render: None,
+            modulus: None,
+            breakdown: false,
+        };
+        let mut reading: Option<Orientation> = None;
+        let mut left_to_right: Option<bool> = None;
+        let mut top_down: Option<bool> = None;
+        let mut operator: Option<OperatorPosition> = None;
+
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let mut value = || iter.next().unwrap_or_else(|| panic!("Missing value for {}", flag)).as_str();
+            match flag.as_str() {
+                "--input" => options.input_path = value().to_string(),
+                "--render" => {
+                    options.render = Some(match value() {
+                        "rows" => Orientation::ROW_WISE,
+                        "columns" => Orientation::COLUMN_WISE,
+                        other => panic!("Unknown render orientation: {}", other),
+                    })
+                }
+                "--modulus" => {
+                    let modulus: BigInt = value().parse().expect("Invalid modulus");
+                    assert!(modulus > BigInt::from(0), "Modulus must be positive");
+                    options.modulus = Some(modulus);
+                }
+                "--breakdown" => options.breakdown = true,
+                "--reading" => {
+                    reading = Some(match value() {
+                        "rows" => Orientation::ROW_WISE,
+                        "columns" => Orientation::COLUMN_WISE,
+                        other => panic!("Unknown reading: {}", other),
+                    })
+                }
+                "--columns" => {
+                    left_to_right = Some(match value() {
+                        "ltr" => true,
+                        "rtl" => false,
+                        other => panic!("Unknown column order: {}", other),
+                    })
+                }
+                "--rows" => {
+                    top_down = Some(match value() {
+                        "top-down" => true,
+                        "bottom-up" => false,
+                        other => panic!("Unknown row order: {}", other),
+                    })
+                }
+                "--operator" => {
+                    operator = Some(match value() {
+                        "top" => OperatorPosition::Top,
+                        "bottom" => OperatorPosition::Bottom,
+                        "left" => OperatorPosition::Left,
+                        other => panic!("Unknown operator position: {}", other),
+                    })
+                }
+                other => panic!("Unknown argument: {}", other),
+            }
+        }
+
+        if reading.is_some() || left_to_right.is_some() || top_down.is_some() || operator.is_some() {
+            let mut orientation = reading.unwrap_or(Orientation::ROW_WISE);
+            orientation.left_to_right = left_to_right.unwrap_or(orientation.left_to_right);
+            orientation.top_down = top_down.unwrap_or(orientation.top_down);
+            orientation.operator = operator.unwrap_or(orientation.operator);
+            options.orientation = Some(orientation);
+        }
+        options
+    }
+}
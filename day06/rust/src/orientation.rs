@@ -0,0 +1,41 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    // Each row of a problem holds one number.
+    Rows,
+    // Each column of a problem holds one number.
+    Columns,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperatorPosition {
+    Top,
+    Bottom,
+    Left,
+}
+
+// How a problem block is read. Whichever axis carries the numbers, the other
+// direction orders their digits: row-wise numbers read their digits along the
+// column order and are taken in row order, column-wise numbers the reverse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Orientation {
+    pub numbers_along: Axis,
+    pub left_to_right: bool,
+    pub top_down: bool,
+    pub operator: OperatorPosition,
+}
+
+impl Orientation {
+    pub const ROW_WISE: Orientation = Orientation {
+        numbers_along: Axis::Rows,
+        left_to_right: true,
+        top_down: true,
+        operator: OperatorPosition::Bottom,
+    };
+
+    pub const COLUMN_WISE: Orientation = Orientation {
+        numbers_along: Axis::Columns,
+        left_to_right: false,
+        top_down: true,
+        operator: OperatorPosition::Bottom,
+    };
+}
@@ -0,0 +1,172 @@
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, ToPrimitive, Zero};
+use std::fmt;
+
+pub struct Problem {
+    // Operands in reading order.
+    pub numbers: Vec<BigInt>,
+    pub operation: char,
+    // Half-open worksheet column span the problem was read from.
+    pub columns: (usize, usize),
+}
+
+#[derive(Debug)]
+pub enum EvalError {
+    UnknownOperator { symbol: char, columns: (usize, usize) },
+    MissingOperands { symbol: char, columns: (usize, usize) },
+    DivisionByZero { columns: (usize, usize) },
+    ExponentOutOfRange { exponent: BigInt, columns: (usize, usize) },
+    NotInvertible { divisor: BigInt, modulus: BigInt, columns: (usize, usize) },
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::UnknownOperator { symbol, columns } => {
+                write!(f, "unknown operator '{}' in columns {}..{}", symbol, columns.0, columns.1)
+            }
+            EvalError::MissingOperands { symbol, columns } => {
+                write!(f, "operator '{}' has no operands in columns {}..{}", symbol, columns.0, columns.1)
+            }
+            EvalError::DivisionByZero { columns } => {
+                write!(f, "division by zero in columns {}..{}", columns.0, columns.1)
+            }
+            EvalError::ExponentOutOfRange { exponent, columns } => {
+                write!(f, "exponent {} out of range in columns {}..{}", exponent, columns.0, columns.1)
+            }
+            EvalError::NotInvertible { divisor, modulus, columns } => write!(
+                f,
+                "{} has no inverse modulo {} in columns {}..{}",
+                divisor, modulus, columns.0, columns.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+// Largest power `^` will build exactly: 2^26 bits, or 8 MiB.
+const MAX_POWER_BITS: u64 = 1 << 26;
+
+pub fn is_operator_symbol(c: char) -> bool {
+    !c.is_ascii_digit() && !c.is_whitespace()
+}
+
+// Operators fold left over the operands in reading order, so `-`, `/`, `%` and
+// `^` read as `a op b op c` = `(a op b) op c`. Division is exact; everything
+// else stays integral.
+pub fn evaluate_problem(problem: &Problem) -> Result<BigRational, EvalError> {
+    let columns = problem.columns;
+    let numbers = &problem.numbers;
+
+    let (first, rest) = match (problem.operation, numbers.split_first()) {
+        ('+', _) => return Ok(BigRational::from_integer(numbers.iter().sum())),
+        ('*', _) => return Ok(BigRational::from_integer(numbers.iter().product())),
+        ('-' | '/' | '^' | '%', Some(split)) => split,
+        (symbol @ ('-' | '/' | '^' | '%'), None) => {
+            return Err(EvalError::MissingOperands { symbol, columns });
+        }
+        (symbol, _) => return Err(EvalError::UnknownOperator { symbol, columns }),
+    };
+
+    match problem.operation {
+        '-' => Ok(BigRational::from_integer(rest.iter().fold(first.clone(), |acc, n| acc - n))),
+        '/' => rest.iter().try_fold(BigRational::from_integer(first.clone()), |acc, n| {
+            if n.is_zero() {
+                return Err(EvalError::DivisionByZero { columns });
+            }
+            Ok(acc / BigRational::from_integer(n.clone()))
+        }),
+        '%' => rest
+            .iter()
+            .try_fold(first.clone(), |acc, n| {
+                if n.is_zero() {
+                    return Err(EvalError::DivisionByZero { columns });
+                }
+                Ok(acc % n)
+            })
+            .map(BigRational::from_integer),
+        '^' => rest
+            .iter()
+            .try_fold(first.clone(), |acc, n| {
+                // The power has at most `bits * exponent` bits; anything past the
+                // limit is refused, except for the trivial bases which the guard
+                // lets through.
+                let exponent = n
+                    .to_u32()
+                    .filter(|&exponent| acc.bits().saturating_mul(exponent.into()) <= MAX_POWER_BITS);
+                match exponent {
+                    Some(exponent) => Ok(acc.pow(exponent)),
+                    None if acc.is_zero() || acc.is_one() => Ok(acc),
+                    None => Err(EvalError::ExponentOutOfRange { exponent: n.clone(), columns }),
+                }
+            })
+            .map(BigRational::from_integer),
+        _ => unreachable!("operator validated above"),
+    }
+}
+
+// Same operators, reduced modulo `modulus` (which must be positive) as they go
+// so long products and powers never materialize. Division multiplies by the
+// modular inverse; `-` and `%` are taken exactly first since their
+// intermediates never outgrow the operands.
+pub fn evaluate_problem_mod(problem: &Problem, modulus: &BigInt) -> Result<BigInt, EvalError> {
+    let columns = problem.columns;
+    let numbers = &problem.numbers;
+    let reduce = |value: BigInt| ((value % modulus) + modulus) % modulus;
+
+    match problem.operation {
+        '+' => return Ok(numbers.iter().fold(BigInt::zero(), |acc, n| reduce(acc + n))),
+        '*' => return Ok(numbers.iter().fold(reduce(BigInt::one()), |acc, n| reduce(acc * n))),
+        '-' | '%' => {
+            return evaluate_problem(problem).map(|exact| reduce(exact.to_integer()));
+        }
+        '/' | '^' => {}
+        symbol => return Err(EvalError::UnknownOperator { symbol, columns }),
+    }
+
+    let Some((first, rest)) = numbers.split_first() else {
+        return Err(EvalError::MissingOperands { symbol: problem.operation, columns });
+    };
+
+    if problem.operation == '^' {
+        return Ok(rest.iter().fold(reduce(first.clone()), |acc, n| acc.modpow(n, modulus)));
+    }
+
+    rest.iter().try_fold(reduce(first.clone()), |acc, n| {
+        if n.is_zero() {
+            return Err(EvalError::DivisionByZero { columns });
+        }
+        let inverse = n.modinv(modulus).ok_or_else(|| EvalError::NotInvertible {
+            divisor: n.clone(),
+            modulus: modulus.clone(),
+            columns,
+        })?;
+        Ok(reduce(acc * inverse))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn power(base: i64, exponent: u64) -> Result<BigRational, EvalError> {
+        evaluate_problem(&Problem {
+            numbers: vec![BigInt::from(base), BigInt::from(exponent)],
+            operation: '^',
+            columns: (0, 3),
+        })
+    }
+
+    #[test]
+    fn powers_are_bounded() {
+        assert_eq!(power(2, 10).unwrap(), BigRational::from_integer(1024.into()));
+        assert_eq!(power(2, 1 << 25).unwrap().to_integer().bits(), (1 << 25) + 1);
+        assert!(matches!(power(2, 4_000_000_000), Err(EvalError::ExponentOutOfRange { .. })));
+        assert!(matches!(power(3, 1 << 26), Err(EvalError::ExponentOutOfRange { .. })));
+        assert!(matches!(power(7, 1 << 40), Err(EvalError::ExponentOutOfRange { .. })));
+        assert!(power(1, 4_000_000_000).unwrap().is_one());
+        assert!(power(0, 1 << 40).unwrap().is_zero());
+    }
+}
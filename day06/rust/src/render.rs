@@ -0,0 +1,92 @@
+use crate::orientation::{Axis, OperatorPosition, Orientation};
+use crate::problem::Problem;
+
+// Lays problems out as a worksheet that reads back, in the same orientation, to
+// the same operands and operators. Problems are separated by one blank column;
+// numbers are packed against the top-left of their block, which parsing
+// ignores since it only collects digits.
+pub fn render(problems: &[Problem], orientation: &Orientation) -> String {
+    let number_rows = match orientation.numbers_along {
+        Axis::Rows => problems.iter().map(|p| p.numbers.len()).max().unwrap_or(0),
+        Axis::Columns => problems
+            .iter()
+            .flat_map(|p| &p.numbers)
+            .map(|n| n.to_string().len())
+            .max()
+            .unwrap_or(0),
+    };
+    let height = match orientation.operator {
+        OperatorPosition::Top | OperatorPosition::Bottom => number_rows + 1,
+        OperatorPosition::Left => number_rows,
+    };
+
+    let mut lines: Vec<Vec<u8>> = vec![Vec::new(); height];
+    for (index, problem) in problems.iter().enumerate() {
+        let block = render_block(problem, orientation, number_rows, height);
+        for (line, block_row) in lines.iter_mut().zip(block) {
+            if index > 0 {
+                line.push(b' ');
+            }
+            line.extend(block_row);
+        }
+    }
+
+    lines
+        .iter()
+        .map(|line| String::from_utf8_lossy(line).trim_end().to_string() + "\n")
+        .collect()
+}
+
+fn render_block(
+    problem: &Problem,
+    orientation: &Orientation,
+    number_rows: usize,
+    height: usize,
+) -> Vec<Vec<u8>> {
+    let digit_strings: Vec<Vec<u8>> = problem
+        .numbers
+        .iter()
+        .map(|n| n.to_string().into_bytes())
+        .collect();
+
+    let (row_offset, operator_cell) = match orientation.operator {
+        OperatorPosition::Top => (1, (0, 0)),
+        OperatorPosition::Bottom => (0, (number_rows, 0)),
+        OperatorPosition::Left => (0, (0, 0)),
+    };
+    let col_offset = usize::from(orientation.operator == OperatorPosition::Left);
+
+    let number_width = match orientation.numbers_along {
+        Axis::Rows => digit_strings.iter().map(Vec::len).max().unwrap_or(0),
+        Axis::Columns => digit_strings.len(),
+    };
+    let width = (col_offset + number_width).max(1);
+    let mut block = vec![vec![b' '; width]; height];
+
+    let count = digit_strings.len();
+    for (index, mut digits) in digit_strings.into_iter().enumerate() {
+        match orientation.numbers_along {
+            Axis::Rows => {
+                let row = if orientation.top_down { index } else { number_rows - 1 - index };
+                if !orientation.left_to_right {
+                    digits.reverse();
+                }
+                let cells = &mut block[row_offset + row][col_offset..col_offset + digits.len()];
+                cells.copy_from_slice(&digits);
+            }
+            Axis::Columns => {
+                let col = if orientation.left_to_right { index } else { count - 1 - index };
+                if !orientation.top_down {
+                    digits.reverse();
+                }
+                for (row, digit) in digits.into_iter().enumerate() {
+                    block[row_offset + row][col_offset + col] = digit;
+                }
+            }
+        }
+    }
+
+    let (operator_row, operator_col) = operator_cell;
+    block[operator_row][operator_col] = problem.operation as u8;
+    block
+}
@@ -0,0 +1,79 @@
+// The worksheet as a rectangular byte matrix, padded with spaces, stored both
+// row-major and column-major so either reading direction is a contiguous slice.
+pub struct Worksheet {
+    width: usize,
+    height: usize,
+    rows: Vec<u8>,
+    columns: Vec<u8>,
+    // Half-open column spans of the problems, i.e. the runs of non-blank columns.
+    blocks: Vec<(usize, usize)>,
+}
+
+impl Worksheet {
+    pub fn parse(input: &str) -> Self {
+        let lines: Vec<&[u8]> = input
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(str::as_bytes)
+            .collect();
+        let height = lines.len();
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+
+        let mut rows = vec![b' '; width * height];
+        let mut columns = vec![b' '; width * height];
+        for (row, line) in lines.iter().enumerate() {
+            rows[row * width..row * width + line.len()].copy_from_slice(line);
+            for (col, &byte) in line.iter().enumerate() {
+                columns[col * height + row] = byte;
+            }
+        }
+
+        let mut sheet = Worksheet {
+            width,
+            height,
+            rows,
+            columns,
+            blocks: Vec::new(),
+        };
+        sheet.blocks = sheet.find_blocks();
+        sheet
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn row(&self, row: usize) -> &[u8] {
+        &self.rows[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn column(&self, col: usize) -> &[u8] {
+        &self.columns[col * self.height..(col + 1) * self.height]
+    }
+
+    pub fn blocks(&self) -> &[(usize, usize)] {
+        &self.blocks
+    }
+
+    fn find_blocks(&self) -> Vec<(usize, usize)> {
+        let mut blocks: Vec<(usize, usize)> = Vec::new();
+        let mut block_start: Option<usize> = None;
+
+        for col in 0..self.width {
+            let is_blank = self.column(col).iter().all(|&byte| byte == b' ');
+            match (block_start, is_blank) {
+                (None, false) => block_start = Some(col),
+                (Some(start), true) => {
+                    blocks.push((start, col));
+                    block_start = None;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(start) = block_start {
+            blocks.push((start, self.width));
+        }
+        blocks
+    }
+}
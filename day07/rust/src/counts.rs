@@ -0,0 +1,148 @@
+use num_bigint::BigInt;
+use std::fmt;
+use std::ops::AddAssign;
+
+// A number of timelines. Counts only ever start from one and get added together,
+// so that is all an implementation has to support.
+pub trait Count: Clone + fmt::Display + for<'a> AddAssign<&'a Self> {
+    // Whether the count no longer holds the true value.
+    fn overflowed(&self) -> bool {
+        false
+    }
+}
+
+impl Count for BigInt {}
+
+// A `u64` count that remembers when an addition overflowed instead of wrapping.
+#[derive(Clone, Copy, Debug)]
+pub struct Checked(Option<u64>);
+
+impl Checked {
+    pub fn one() -> Self {
+        Checked(Some(1))
+    }
+}
+
+impl AddAssign<&Checked> for Checked {
+    fn add_assign(&mut self, other: &Checked) {
+        self.0 = self.0.zip(other.0).and_then(|(a, b)| a.checked_add(b));
+    }
+}
+
+impl fmt::Display for Checked {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(count) => write!(f, "{}", count),
+            None => write!(f, "overflow"),
+        }
+    }
+}
+
+impl Count for Checked {
+    fn overflowed(&self) -> bool {
+        self.0.is_none()
+    }
+}
+
+// A count reduced modulo `modulus`.
+#[derive(Clone, Copy, Debug)]
+pub struct Modular {
+    value: u64,
+    modulus: u64,
+}
+
+impl Modular {
+    pub fn one(modulus: u64) -> Self {
+        assert!(modulus > 0, "Modulus must be positive");
+        Modular {
+            value: 1 % modulus,
+            modulus,
+        }
+    }
+}
+
+impl AddAssign<&Modular> for Modular {
+    fn add_assign(&mut self, other: &Modular) {
+        self.value = ((self.value as u128 + other.value as u128) % self.modulus as u128) as u64;
+    }
+}
+
+impl fmt::Display for Modular {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl Count for Modular {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optics::{Grid, count_timelines};
+    use num_traits::One;
+
+    // A pyramid of splitters `rows` deep: every beam splits on every row, so
+    // 2^rows timelines reach the bottom.
+    fn pyramid(rows: usize) -> Grid {
+        let width = 2 * rows + 1;
+        let mut text = format!("{}S{}\n", ".".repeat(rows), ".".repeat(rows));
+        for row in 0..rows {
+            let line: String = (0..width)
+                .map(|col| {
+                    if (col + row + rows).is_multiple_of(2) {
+                        '^'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text.push_str(&".".repeat(width));
+        Grid::parse(&text)
+    }
+
+    fn total<T: Count>(grid: &Grid, one: T) -> T {
+        let exits = count_timelines(grid, grid.sources()[0], one).unwrap();
+        let mut counts = exits.into_values();
+        let mut total = counts.next().unwrap();
+        for count in counts {
+            total += &count;
+        }
+        total
+    }
+
+    #[test]
+    fn checked_overflows_past_u64() {
+        let total_63 = total(&pyramid(63), Checked::one());
+        assert!(!total_63.overflowed());
+        assert_eq!(total_63.to_string(), (1u64 << 63).to_string());
+        assert!(total(&pyramid(64), Checked::one()).overflowed());
+        assert!(total(&pyramid(70), Checked::one()).overflowed());
+    }
+
+    #[test]
+    fn modular_matches_big_remainder() {
+        let grid = pyramid(70);
+        let exact = total(&grid, BigInt::one());
+        assert_eq!(exact, BigInt::one() << 70);
+        for modulus in [1, 2, 7, 1_000_000_007, u64::MAX] {
+            let expected = &exact % BigInt::from(modulus);
+            assert_eq!(
+                total(&grid, Modular::one(modulus)).to_string(),
+                expected.to_string()
+            );
+        }
+
+        // Each exit's count reduces independently too.
+        let exits = count_timelines(&grid, grid.sources()[0], BigInt::one()).unwrap();
+        let reduced = count_timelines(&grid, grid.sources()[0], Modular::one(998_244_353)).unwrap();
+        for (exit, count) in exits {
+            assert_eq!(
+                reduced[&exit].to_string(),
+                (count % BigInt::from(998_244_353)).to_string()
+            );
+        }
+    }
+}
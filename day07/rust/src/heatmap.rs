@@ -0,0 +1,117 @@
+use crate::optics::Grid;
+use num_bigint::BigInt;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// Pixels per grid cell in the PPM image.
+const SCALE: usize = 4;
+
+pub struct SplitterStats {
+    pub cell: (usize, usize),
+    // Whether the classical beam reaches it.
+    pub hit: bool,
+    // How many timelines it splits.
+    pub timelines: BigInt,
+}
+
+pub fn splitter_stats(
+    grid: &Grid,
+    hit: &BTreeSet<(usize, usize)>,
+    timelines: &BTreeMap<(usize, usize), BigInt>,
+) -> Vec<SplitterStats> {
+    grid.splitters()
+        .into_iter()
+        .map(|cell| SplitterStats {
+            cell,
+            hit: hit.contains(&cell),
+            timelines: timelines.get(&cell).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
+
+// Timeline counts span hundreds of orders of magnitude, so splitters are shaded
+// by the bit length of their count, from 0 for the least busy reached splitter
+// to 9 for the busiest. Unreached splitters get `Some(None)`, other tiles
+// `None`. Indexed by `row * width + col`.
+fn levels(grid: &Grid, stats: &[SplitterStats]) -> Vec<Option<Option<u64>>> {
+    let bits = |s: &SplitterStats| s.timelines.bits();
+    let least = stats.iter().filter(|s| s.hit).map(bits).min().unwrap_or(0);
+    let most = stats.iter().filter(|s| s.hit).map(bits).max().unwrap_or(0);
+    let range = (most - least).max(1);
+    let mut levels = vec![None; grid.width() * grid.height()];
+    for s in stats {
+        let (row, col) = s.cell;
+        levels[row * grid.width() + col] = Some(s.hit.then(|| (bits(s) - least) * 9 / range));
+    }
+    levels
+}
+
+// The grid with each splitter replaced by its level, or `x` if never reached.
+pub fn render_ascii(grid: &Grid, stats: &[SplitterStats]) -> String {
+    let levels = levels(grid, stats);
+    let mut out = String::new();
+    for row in 0..grid.height() {
+        for col in 0..grid.width() {
+            out.push(match levels[row * grid.width() + col] {
+                Some(Some(level)) => char::from(b'0' + level as u8),
+                Some(None) => 'x',
+                None => grid.tile(row, col) as char,
+            });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// The same map as a binary PPM: splitters run from blue (quiet) to yellow
+// (busy), unreached ones are red, other tiles grey on white. Written a row at
+// a time, since a large grid scaled up does not fit comfortably in memory.
+pub fn write_ppm(grid: &Grid, stats: &[SplitterStats], path: &str) -> io::Result<()> {
+    let levels = levels(grid, stats);
+    let mut out = BufWriter::new(File::create(path)?);
+    write!(
+        out,
+        "P6\n{} {}\n255\n",
+        grid.width() * SCALE,
+        grid.height() * SCALE
+    )?;
+
+    let mut line = Vec::with_capacity(grid.width() * SCALE * 3);
+    for row in 0..grid.height() {
+        line.clear();
+        for col in 0..grid.width() {
+            let pixel = match levels[row * grid.width() + col] {
+                Some(Some(level)) => {
+                    let heat = (level * 255 / 9) as u8;
+                    [heat, heat, 255 - heat]
+                }
+                Some(None) => [220, 30, 30],
+                None => match grid.tile(row, col) {
+                    b'.' => [255, 255, 255],
+                    b'S' => [30, 160, 30],
+                    _ => [150, 150, 150],
+                },
+            };
+            for _ in 0..SCALE {
+                line.extend(pixel);
+            }
+        }
+        for _ in 0..SCALE {
+            out.write_all(&line)?;
+        }
+    }
+    out.flush()
+}
+
+pub fn print_unused(stats: &[SplitterStats]) {
+    let unused: Vec<&SplitterStats> = stats.iter().filter(|s| !s.hit).collect();
+    println!(
+        "Never reached: {} of {} splitters",
+        unused.len(),
+        stats.len()
+    );
+    for s in unused {
+        println!("  row {}, column {}", s.cell.0, s.cell.1);
+    }
+}
@@ -1,102 +1,207 @@
-use std::collections::{HashMap, HashSet};
+mod counts;
+mod heatmap;
+mod optics;
+mod options;
+mod weights;
+
+use counts::{Checked, Count, Modular};
+use heatmap::{print_unused, render_ascii, splitter_stats, write_ppm};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::One;
+use optics::{
+    Exit, ExitCounts, Grid, combine, count_timelines, exit_probabilities, splitter_timelines, trace,
+};
+use options::Options;
+use std::collections::BTreeMap;
+use std::env;
 use std::fs;
+use std::process;
+use weights::SplitterWeights;
 
 fn main() {
-    let input = fs::read_to_string("../input.txt").expect("Failed to read input file");
-    let grid: Vec<&str> = input.lines().collect();
-
-    println!("Part 1: {}", simulate_beam(&grid));
-    println!("Part 2: {}", count_timelines(&grid));
-}
-
-fn simulate_beam(grid: &[&str]) -> usize {
-    let height = grid.len();
-    let width = if height > 0 { grid[0].len() } else { 0 };
-
-    let start_row = grid.iter().position(|line| line.contains('S')).unwrap();
-    let start_col = grid[start_row].find('S').unwrap();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = Options::from_args(&args);
+
+    let input = fs::read_to_string(&options.input_path).expect("Failed to read input file");
+    let grid = Grid::parse(&input);
+    let sources = grid.sources();
+
+    if options.probabilities {
+        let weights = match &options.weights_path {
+            Some(path) => SplitterWeights::parse(
+                &fs::read_to_string(path).expect("Failed to read weights file"),
+            ),
+            None => SplitterWeights::even(),
+        };
+        for &(row, col) in &sources {
+            match exit_probabilities(&grid, (row, col), &weights) {
+                Ok(distribution) => print_distribution(
+                    &format!("Source at row {}, column {}", row, col),
+                    &distribution,
+                ),
+                Err(err) => {
+                    println!("Source at row {}, column {}: {}", row, col, err);
+                    process::exit(1);
+                }
+            }
+        }
+        return;
+    }
 
-    let mut active_columns: HashSet<i32> = HashSet::new();
-    active_columns.insert(start_col as i32);
+    let classical = trace(&grid, &sources);
+    println!("Part 1: {}", classical.splits);
+    if classical.looping {
+        println!("(the classical beam is caught in a loop)");
+    }
 
-    let mut total_splitter_hits = 0;
+    let reported = match options.modulus {
+        Some(modulus) => report_timelines(&grid, &sources, Modular::one(modulus), &options),
+        None if options.big => report_timelines(&grid, &sources, BigInt::one(), &options),
+        None => report_timelines(&grid, &sources, Checked::one(), &options),
+    };
+
+    if options.heatmap || options.ppm_path.is_some() {
+        let mut timelines: BTreeMap<(usize, usize), BigInt> = BTreeMap::new();
+        for &(row, col) in &sources {
+            // A source feeding a splitting loop has no finite counts to shade.
+            let Ok(per_splitter) = splitter_timelines(&grid, (row, col), BigInt::one()) else {
+                println!(
+                    "Source at row {}, column {} left out of the heatmap",
+                    row, col
+                );
+                continue;
+            };
+            for (cell, count) in per_splitter {
+                *timelines.entry(cell).or_default() += count;
+            }
+        }
+        let stats = splitter_stats(&grid, &classical.hit_splitters, &timelines);
 
-    for row in (start_row + 1)..height {
-        if active_columns.is_empty() {
-            break;
+        match &options.ppm_path {
+            Some(path) => write_ppm(&grid, &stats, path).expect("Failed to write heatmap image"),
+            None => print!("{}", render_ascii(&grid, &stats)),
         }
+        print_unused(&stats);
+    }
 
-        let new_hits = active_columns
-            .iter()
-            .filter(|&&col| {
-                col >= 0
-                    && (col as usize) < width
-                    && grid[row].chars().nth(col as usize) == Some('^')
-            })
-            .count();
-
-        total_splitter_hits += new_hits;
-
-        let next_columns: HashSet<i32> = active_columns
-            .iter()
-            .flat_map(|&col| {
-                if col >= 0
-                    && (col as usize) < width
-                    && grid[row].chars().nth(col as usize) == Some('^')
-                {
-                    vec![col - 1, col + 1]
-                } else {
-                    vec![col]
-                }
-            })
-            .filter(|&col| col >= 0 && (col as usize) < width)
-            .collect();
+    if !reported {
+        process::exit(1);
+    }
+}
 
-        active_columns = next_columns;
+// Part 2 leaves out timelines split off the left or right edge, as the puzzle
+// does; `--side-exits` counts them too. `--exits` always lists them. Returns
+// false if the count is unbounded or overflowed, so main can still draw the
+// heatmap before exiting with an error.
+fn report_timelines<T: Count>(
+    grid: &Grid,
+    sources: &[(usize, usize)],
+    one: T,
+    options: &Options,
+) -> bool {
+    let per_source: Vec<ExitCounts<T>> = match sources
+        .iter()
+        .map(|&source| count_timelines(grid, source, one.clone()))
+        .collect()
+    {
+        Ok(per_source) => per_source,
+        Err(err) => {
+            println!("Part 2: unbounded, {}", err);
+            return false;
+        }
+    };
+    let combined = combine(&per_source);
+    let total = counted_total(&combined, options.side_exits);
+    if total.as_ref().is_some_and(Count::overflowed) || combined.values().any(Count::overflowed) {
+        println!("Part 2: too many timelines for u64, rerun with --big or --modulus P");
+        return false;
     }
+    println!("Part 2: {}", display_total(&total));
 
-    total_splitter_hits
+    if options.show_exits {
+        if sources.len() > 1 {
+            for (&(row, col), exits) in sources.iter().zip(&per_source) {
+                print_exits(&format!("Source at row {}, column {}", row, col), exits);
+            }
+        }
+        print_exits("All sources", &combined);
+    }
+    true
 }
 
-fn count_timelines(grid: &[&str]) -> u64 {
-    let height = grid.len();
-    let width = if height > 0 { grid[0].len() } else { 0 };
+// The Part 2 total: every exit count, but side exits only if asked for.
+fn counted_total<T: Count>(exits: &ExitCounts<T>, side_exits: bool) -> Option<T> {
+    let counted: ExitCounts<T> = exits
+        .iter()
+        .filter(|(exit, _)| side_exits || !exit.is_side())
+        .map(|(&exit, count)| (exit, count.clone()))
+        .collect();
+    total(&counted)
+}
 
-    let start_row = grid.iter().position(|line| line.contains('S')).unwrap();
-    let start_col = grid[start_row].find('S').unwrap();
+// The sum of all exit counts, or `None` if no timeline leaves the grid.
+fn total<T: Count>(exits: &ExitCounts<T>) -> Option<T> {
+    let mut counts = exits.values();
+    let mut total = counts.next()?.clone();
+    for count in counts {
+        total += count;
+    }
+    Some(total)
+}
 
-    let mut timeline_count_by_column: HashMap<i32, u64> = HashMap::new();
-    timeline_count_by_column.insert(start_col as i32, 1);
+fn display_total<T: Count>(total: &Option<T>) -> String {
+    total.as_ref().map_or("0".to_string(), T::to_string)
+}
 
-    for row in (start_row + 1)..height {
-        if timeline_count_by_column.is_empty() {
-            break;
-        }
+fn print_exits<T: Count>(label: &str, exits: &ExitCounts<T>) {
+    println!("{}: {} timelines", label, display_total(&total(exits)));
+    for (exit, count) in exits {
+        println!("  {}: {}", exit, count);
+    }
+}
 
-        let mut new_counts: HashMap<i32, u64> = HashMap::new();
+fn print_distribution(label: &str, distribution: &BTreeMap<Exit, BigRational>) {
+    let total: BigRational = distribution.values().sum();
+    println!("{}:", label);
+    for (exit, probability) in distribution {
+        println!("  {}: {}", exit, probability);
+    }
+    println!("  lost: {}", BigRational::one() - total);
+}
 
-        for (&col, &count) in &timeline_count_by_column {
-            if col >= 0
-                && (col as usize) < width
-                && grid[row].chars().nth(col as usize) == Some('^')
-            {
-                // Split: timeline goes both left and right
-                if col - 1 >= 0 {
-                    *new_counts.entry(col - 1).or_insert(0) += count;
-                }
-                if (col + 1) < width as i32 {
-                    *new_counts.entry(col + 1).or_insert(0) += count;
-                }
-            } else {
-                *new_counts.entry(col).or_insert(0) += count;
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        timeline_count_by_column = new_counts
+    fn part_two(input: &str, side_exits: bool) -> u64 {
+        let grid = Grid::parse(input);
+        let per_source: Vec<ExitCounts<BigInt>> = grid
+            .sources()
             .into_iter()
-            .filter(|&(col, _)| col >= 0 && (col as usize) < width)
+            .map(|source| count_timelines(&grid, source, BigInt::one()).unwrap())
             .collect();
+        counted_total(&combine(&per_source), side_exits)
+            .map_or(0, |total| total.try_into().unwrap())
+    }
+
+    #[test]
+    fn example_manifold() {
+        let input = ".......S.......\n...............\n.......^.......\n...............\n\
+                     ......^.^......\n...............\n.....^.^.^.....\n...............\n\
+                     ....^.^...^....\n...............\n...^.^...^.^...\n...............\n\
+                     ..^...^.....^..\n...............\n.^.^.^.^.^...^.\n...............\n";
+        let grid = Grid::parse(input);
+        assert_eq!(trace(&grid, &grid.sources()).splits, 21);
+        assert_eq!(part_two(input, false), 40);
     }
 
-    timeline_count_by_column.values().sum()
+    // Timelines split off the left or right edge are dropped unless asked for.
+    #[test]
+    fn side_exits_left_out_by_default() {
+        assert_eq!(part_two("S\n^\n", false), 0);
+        assert_eq!(part_two("S\n^\n", true), 2);
+        assert_eq!(part_two("S..\n^..\n...\n", false), 1);
+        assert_eq!(part_two("S..\n^..\n...\n", true), 2);
+    }
 }
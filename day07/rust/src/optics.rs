@@ -0,0 +1,627 @@
+use crate::counts::Count;
+use crate::weights::SplitterWeights;
+use num_rational::BigRational;
+use num_traits::One;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::mem;
+use std::ops::AddAssign;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+        }
+    }
+}
+
+// Where a beam leaves the grid: the edge it crosses and the row or column it
+// crosses it at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Exit {
+    Top(usize),
+    Bottom(usize),
+    Left(usize),
+    Right(usize),
+}
+
+impl Exit {
+    // Whether the beam leaves through the left or right edge rather than the
+    // top or bottom.
+    pub fn is_side(self) -> bool {
+        matches!(self, Exit::Left(_) | Exit::Right(_))
+    }
+}
+
+impl fmt::Display for Exit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Exit::Top(col) => write!(f, "top column {}", col),
+            Exit::Bottom(col) => write!(f, "bottom column {}", col),
+            Exit::Left(row) => write!(f, "left row {}", row),
+            Exit::Right(row) => write!(f, "right row {}", row),
+        }
+    }
+}
+
+// A beam about to enter the cell at (row, col) heading in `direction`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Beam {
+    row: usize,
+    col: usize,
+    direction: Direction,
+}
+
+enum Step {
+    Enter(Beam),
+    Leave(Exit),
+}
+
+// The tiles a beam can meet:
+//   . S     empty space, passed straight through
+//   / \     mirrors, turning the beam by 90 degrees
+//   |       splits horizontal beams up and down, passes vertical ones
+//   -       splits vertical beams left and right, passes horizontal ones
+//   ^       splits downward beams into the two neighbouring columns, which
+//           carry on down from the row below; other beams pass through
+//   #       absorbs the beam
+//
+// The tiles are kept row-major in one array, padded with `.` to a rectangle.
+pub struct Grid {
+    cells: Vec<u8>,
+    width: usize,
+    height: usize,
+}
+
+impl Grid {
+    pub fn parse(input: &str) -> Self {
+        let lines: Vec<&[u8]> = input
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(str::as_bytes)
+            .collect();
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+        let height = lines.len();
+
+        let mut cells = vec![b'.'; width * height];
+        for (row, line) in lines.iter().enumerate() {
+            if let Some(&tile) = line.iter().find(|&&tile| !b".S/\\|-^#".contains(&tile)) {
+                panic!("Unknown tile: {}", tile as char);
+            }
+            cells[row * width..row * width + line.len()].copy_from_slice(line);
+        }
+        Grid {
+            cells,
+            width,
+            height,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn tile(&self, row: usize, col: usize) -> u8 {
+        self.cells[row * self.width + col]
+    }
+
+    // Every `S` in the grid, in reading order.
+    pub fn sources(&self) -> Vec<(usize, usize)> {
+        let sources = self.cells_with(b"S");
+        assert!(!sources.is_empty(), "No source in grid");
+        sources
+    }
+
+    // Every `^`, `|` and `-`, in reading order.
+    pub fn splitters(&self) -> Vec<(usize, usize)> {
+        self.cells_with(b"^|-")
+    }
+
+    fn cells_with(&self, tiles: &[u8]) -> Vec<(usize, usize)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| tiles.contains(tile))
+            .map(|(index, _)| (index / self.width, index % self.width))
+            .collect()
+    }
+
+    // Whether no tile can turn a beam, so every beam heads down and the grid can
+    // be swept one row at a time.
+    fn is_downward(&self) -> bool {
+        !self.cells.iter().any(|tile| b"/\\|-".contains(tile))
+    }
+
+    // A dense index for each (cell, direction) pair.
+    fn beam_index(&self, beam: Beam) -> usize {
+        (beam.row * self.width + beam.col) * 4 + beam.direction as usize
+    }
+
+    fn beam_states(&self) -> usize {
+        self.cells.len() * 4
+    }
+
+    // The beam a source emits: it heads down from the cell below the `S`.
+    fn emit(&self, (row, col): (usize, usize)) -> Step {
+        self.step(row, col, (1, 0), Direction::Down)
+    }
+
+    fn step(&self, row: usize, col: usize, (dr, dc): (isize, isize), direction: Direction) -> Step {
+        let next_row = row as isize + dr;
+        let next_col = col as isize + dc;
+        let last_row = self.height.saturating_sub(1);
+        if next_col < 0 {
+            Step::Leave(Exit::Left((next_row.max(0) as usize).min(last_row)))
+        } else if next_col as usize >= self.width {
+            Step::Leave(Exit::Right((next_row.max(0) as usize).min(last_row)))
+        } else if next_row < 0 {
+            Step::Leave(Exit::Top(next_col as usize))
+        } else if next_row as usize >= self.height {
+            Step::Leave(Exit::Bottom(next_col as usize))
+        } else {
+            Step::Enter(Beam {
+                row: next_row as usize,
+                col: next_col as usize,
+                direction,
+            })
+        }
+    }
+
+    // What becomes of a beam entering a cell: no steps if it is absorbed, two if
+    // it is split.
+    fn interact(&self, beam: Beam) -> [Option<Step>; 2] {
+        use Direction::*;
+
+        let tile = self.tile(beam.row, beam.col);
+        let turned = |direction: Direction| {
+            Some(self.step(beam.row, beam.col, direction.delta(), direction))
+        };
+        match (tile, beam.direction) {
+            (b'#', _) => [None, None],
+            (b'/', Up) | (b'\\', Down) => [turned(Right), None],
+            (b'/', Down) | (b'\\', Up) => [turned(Left), None],
+            (b'/', Left) | (b'\\', Right) => [turned(Down), None],
+            (b'/', Right) | (b'\\', Left) => [turned(Up), None],
+            (b'|', Left | Right) => [turned(Up), turned(Down)],
+            (b'-', Up | Down) => [turned(Left), turned(Right)],
+            (b'^', Down) => [
+                Some(self.step(beam.row, beam.col, (1, -1), Down)),
+                Some(self.step(beam.row, beam.col, (1, 1), Down)),
+            ],
+            _ => [turned(beam.direction), None],
+        }
+    }
+
+    fn successors(&self, beam: Beam) -> impl Iterator<Item = Beam> {
+        self.interact(beam)
+            .into_iter()
+            .flatten()
+            .filter_map(|step| match step {
+                Step::Enter(next) => Some(next),
+                Step::Leave(_) => None,
+            })
+    }
+}
+
+// The classical picture: beams that meet merge, including beams from different
+// sources, so each (cell, direction) is followed once.
+pub struct Trace {
+    pub splits: usize,
+    pub hit_splitters: BTreeSet<(usize, usize)>,
+    pub exits: BTreeSet<Exit>,
+    pub looping: bool,
+}
+
+pub fn trace(grid: &Grid, sources: &[(usize, usize)]) -> Trace {
+    let mut result = Trace {
+        splits: 0,
+        hit_splitters: BTreeSet::new(),
+        exits: BTreeSet::new(),
+        looping: false,
+    };
+    if grid.is_downward() {
+        trace_rows(grid, sources, &mut result);
+        return result;
+    }
+
+    let mut seen = vec![false; grid.beam_states()];
+    let mut visited: Vec<Beam> = Vec::new();
+    let mut queue: VecDeque<Step> = sources.iter().map(|&source| grid.emit(source)).collect();
+
+    while let Some(step) = queue.pop_front() {
+        match step {
+            Step::Leave(exit) => {
+                result.exits.insert(exit);
+            }
+            Step::Enter(beam) => {
+                let index = grid.beam_index(beam);
+                if seen[index] {
+                    continue;
+                }
+                seen[index] = true;
+                visited.push(beam);
+                let next = grid.interact(beam);
+                if next[1].is_some() {
+                    result.splits += 1;
+                    result.hit_splitters.insert((beam.row, beam.col));
+                }
+                queue.extend(next.into_iter().flatten());
+            }
+        }
+    }
+
+    result.looping = topological_order(grid, &visited).len() < visited.len();
+    result
+}
+
+// The classical picture on a downward grid, keeping one flag per column for the
+// beams entering the current row.
+fn trace_rows(grid: &Grid, sources: &[(usize, usize)], result: &mut Trace) {
+    let mut active = vec![false; grid.width];
+    let mut next = vec![false; grid.width];
+
+    for row in 0..grid.height {
+        for col in 0..grid.width {
+            if !active[col] {
+                continue;
+            }
+            match grid.tile(row, col) {
+                b'^' => {
+                    result.splits += 1;
+                    result.hit_splitters.insert((row, col));
+                    for target in [col.wrapping_sub(1), col + 1] {
+                        match row_target(grid, row, target) {
+                            Ok(target) => next[target] = true,
+                            Err(exit) => {
+                                result.exits.insert(exit);
+                            }
+                        }
+                    }
+                }
+                b'#' => {}
+                _ => next[col] = true,
+            }
+        }
+        for &(_, col) in sources.iter().filter(|&&(source_row, _)| source_row == row) {
+            next[col] = true;
+        }
+        mem::swap(&mut active, &mut next);
+        next.fill(false);
+    }
+
+    for (col, _) in active.iter().enumerate().filter(|&(_, &on)| on) {
+        result.exits.insert(Exit::Bottom(col));
+    }
+}
+
+// The column a beam split off at `row` carries on down in, or the side it leaves
+// by. `col` has wrapped around if the beam went off the left edge.
+fn row_target(grid: &Grid, row: usize, col: usize) -> Result<usize, Exit> {
+    let last_row = grid.height - 1;
+    if col == usize::MAX {
+        Err(Exit::Left((row + 1).min(last_row)))
+    } else if col >= grid.width {
+        Err(Exit::Right((row + 1).min(last_row)))
+    } else {
+        Ok(col)
+    }
+}
+
+#[derive(Debug)]
+pub struct BeamLoop;
+
+impl fmt::Display for BeamLoop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a beam splits inside a loop, so the timelines never end")
+    }
+}
+
+// How many timelines leave the grid through each exit. Timelines that end in an
+// absorber leave through none.
+pub type ExitCounts<T> = BTreeMap<Exit, T>;
+
+// The quantum picture for one source: every split doubles the timelines, which
+// start out as `one`. A loop with a splitter on it means unboundedly many
+// timelines; one without traps the timelines that enter it, which then leave
+// through no exit.
+pub fn count_timelines<T: Count>(
+    grid: &Grid,
+    source: (usize, usize),
+    one: T,
+) -> Result<ExitCounts<T>, BeamLoop> {
+    Ok(flow(grid, source, one, |_, _, count| count.clone())?.exits)
+}
+
+// How many of the timelines from `source` each splitter splits. Splitters no
+// timeline reaches are left out.
+pub fn splitter_timelines<T: Count>(
+    grid: &Grid,
+    source: (usize, usize),
+    one: T,
+) -> Result<BTreeMap<(usize, usize), T>, BeamLoop> {
+    Ok(flow(grid, source, one, |_, _, count| count.clone())?.splitters)
+}
+
+// The chance of a single particle from `source` leaving through each exit, with
+// each splitter's branches taken at the given weights. Whatever is missing from
+// the total is lost to absorbers, lossy splitters and loops.
+pub fn exit_probabilities(
+    grid: &Grid,
+    source: (usize, usize),
+    weights: &SplitterWeights,
+) -> Result<BTreeMap<Exit, BigRational>, BeamLoop> {
+    flow(
+        grid,
+        source,
+        BigRational::one(),
+        |cell, branch, probability| {
+            let (first, second) = weights.at(cell);
+            probability * if branch == 0 { first } else { second }
+        },
+    )
+    .map(|flow| flow.exits)
+}
+
+struct Flow<T> {
+    exits: BTreeMap<Exit, T>,
+    // What passed through each splitter that split it.
+    splitters: BTreeMap<(usize, usize), T>,
+}
+
+// Pushes an amount from the source along the beams in topological order and
+// collects what reaches each exit. `split` gives the share a splitter at a cell
+// sends down its first (0) or second (1) branch.
+fn flow<T>(
+    grid: &Grid,
+    source: (usize, usize),
+    unit: T,
+    split: impl Fn((usize, usize), usize, &T) -> T,
+) -> Result<Flow<T>, BeamLoop>
+where
+    T: Clone + for<'a> AddAssign<&'a T>,
+{
+    if grid.is_downward() {
+        return Ok(flow_rows(grid, source, unit, split));
+    }
+
+    let first = match grid.emit(source) {
+        Step::Enter(beam) => beam,
+        Step::Leave(exit) => {
+            return Ok(Flow {
+                exits: BTreeMap::from([(exit, unit)]),
+                splitters: BTreeMap::new(),
+            });
+        }
+    };
+    // Beams Kahn's algorithm never frees lie on a cycle or downstream of one. A
+    // cycle with a split on it multiplies timelines without end, and is the only
+    // kind anything leaves. One without is a closed loop: what enters it never
+    // reaches a beam in `order` and is lost, as at an absorber.
+    let beams = reachable(grid, first);
+    let order = topological_order(grid, &beams);
+    if order.len() < beams.len() {
+        let mut ordered = vec![false; grid.beam_states()];
+        for &beam in &order {
+            ordered[grid.beam_index(beam)] = true;
+        }
+        let splits_in_loop = beams
+            .iter()
+            .any(|&beam| !ordered[grid.beam_index(beam)] && grid.interact(beam)[1].is_some());
+        if splits_in_loop {
+            return Err(BeamLoop);
+        }
+    }
+
+    let mut through: Vec<Option<T>> = vec![None; grid.beam_states()];
+    through[grid.beam_index(first)] = Some(unit);
+    let mut result = Flow {
+        exits: BTreeMap::new(),
+        splitters: BTreeMap::new(),
+    };
+    for beam in order {
+        let amount = through[grid.beam_index(beam)].take().unwrap();
+        let steps = grid.interact(beam);
+        let is_split = steps[1].is_some();
+        if is_split {
+            add_at(&mut result.splitters, (beam.row, beam.col), amount.clone());
+        }
+        for (branch, step) in steps.into_iter().flatten().enumerate() {
+            let share = if is_split {
+                split((beam.row, beam.col), branch, &amount)
+            } else {
+                amount.clone()
+            };
+            match step {
+                Step::Enter(next) => add_to(&mut through[grid.beam_index(next)], share),
+                Step::Leave(exit) => add_at(&mut result.exits, exit, share),
+            }
+        }
+    }
+    Ok(result)
+}
+
+// `flow` on a downward grid, keeping one amount per column for the beams
+// entering the current row.
+fn flow_rows<T>(
+    grid: &Grid,
+    (source_row, source_col): (usize, usize),
+    unit: T,
+    split: impl Fn((usize, usize), usize, &T) -> T,
+) -> Flow<T>
+where
+    T: Clone + for<'a> AddAssign<&'a T>,
+{
+    let mut result = Flow {
+        exits: BTreeMap::new(),
+        splitters: BTreeMap::new(),
+    };
+    let mut current: Vec<Option<T>> = vec![None; grid.width];
+    let mut next: Vec<Option<T>> = vec![None; grid.width];
+    current[source_col] = Some(unit);
+
+    for row in source_row + 1..grid.height {
+        for col in 0..grid.width {
+            let Some(amount) = current[col].take() else {
+                continue;
+            };
+            match grid.tile(row, col) {
+                b'^' => {
+                    let shares = [split((row, col), 0, &amount), split((row, col), 1, &amount)];
+                    add_at(&mut result.splitters, (row, col), amount);
+                    for (target, share) in [col.wrapping_sub(1), col + 1].into_iter().zip(shares) {
+                        match row_target(grid, row, target) {
+                            Ok(target) => add_to(&mut next[target], share),
+                            Err(exit) => add_at(&mut result.exits, exit, share),
+                        }
+                    }
+                }
+                b'#' => {}
+                _ => add_to(&mut next[col], amount),
+            }
+        }
+        mem::swap(&mut current, &mut next);
+    }
+
+    for (col, amount) in current.into_iter().enumerate() {
+        if let Some(amount) = amount {
+            add_at(&mut result.exits, Exit::Bottom(col), amount);
+        }
+    }
+    result
+}
+
+fn add_to<T>(slot: &mut Option<T>, amount: T)
+where
+    T: for<'a> AddAssign<&'a T>,
+{
+    match slot {
+        Some(total) => *total += &amount,
+        None => *slot = Some(amount),
+    }
+}
+
+fn add_at<K: Ord, T>(totals: &mut BTreeMap<K, T>, key: K, amount: T)
+where
+    T: for<'a> AddAssign<&'a T>,
+{
+    match totals.get_mut(&key) {
+        Some(total) => *total += &amount,
+        None => {
+            totals.insert(key, amount);
+        }
+    }
+}
+
+// Timelines from independent sources never interact, so their exit counts add.
+pub fn combine<T: Count>(per_source: &[ExitCounts<T>]) -> ExitCounts<T> {
+    let mut combined = ExitCounts::new();
+    for exits in per_source {
+        for (&exit, count) in exits {
+            combined
+                .entry(exit)
+                .and_modify(|total: &mut T| *total += count)
+                .or_insert_with(|| count.clone());
+        }
+    }
+    combined
+}
+
+fn reachable(grid: &Grid, first: Beam) -> Vec<Beam> {
+    let mut seen = vec![false; grid.beam_states()];
+    seen[grid.beam_index(first)] = true;
+    let mut beams = vec![first];
+    let mut stack = vec![first];
+    while let Some(beam) = stack.pop() {
+        for next in grid.successors(beam) {
+            let index = grid.beam_index(next);
+            if !seen[index] {
+                seen[index] = true;
+                beams.push(next);
+                stack.push(next);
+            }
+        }
+    }
+    beams
+}
+
+// Kahn's algorithm over the beam states. Beams on a cycle, or reached only
+// through one, are left out.
+fn topological_order(grid: &Grid, beams: &[Beam]) -> Vec<Beam> {
+    let mut incoming = vec![0u32; grid.beam_states()];
+    for &beam in beams {
+        for next in grid.successors(beam) {
+            incoming[grid.beam_index(next)] += 1;
+        }
+    }
+
+    let mut ready: Vec<Beam> = beams
+        .iter()
+        .copied()
+        .filter(|&beam| incoming[grid.beam_index(beam)] == 0)
+        .collect();
+    let mut order = Vec::with_capacity(beams.len());
+    while let Some(beam) = ready.pop() {
+        order.push(beam);
+        for next in grid.successors(beam) {
+            let remaining = &mut incoming[grid.beam_index(next)];
+            *remaining -= 1;
+            if *remaining == 0 {
+                ready.push(next);
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counts::Checked;
+
+    fn timelines(input: &str) -> Result<ExitCounts<Checked>, BeamLoop> {
+        let grid = Grid::parse(input);
+        count_timelines(&grid, grid.sources()[0], Checked::one())
+    }
+
+    // The split sends both timelines into a loop of mirrors and a '-' crossed
+    // lengthwise, which traps them without splitting again.
+    #[test]
+    fn loop_without_split_traps_timelines() {
+        let input = "/S.\\\n\\-./\n";
+        assert!(timelines(input).unwrap().is_empty());
+
+        let grid = Grid::parse(input);
+        assert!(trace(&grid, &grid.sources()).looping);
+        let weights = SplitterWeights::even();
+        assert!(
+            exit_probabilities(&grid, grid.sources()[0], &weights)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    // Here the loop runs back down into the '-' it split at.
+    #[test]
+    fn loop_with_split_is_unbounded() {
+        let input = "../\\.\n..S..\n..-/.\n";
+        assert!(timelines(input).is_err());
+        let grid = Grid::parse(input);
+        let weights = SplitterWeights::even();
+        assert!(exit_probabilities(&grid, grid.sources()[0], &weights).is_err());
+    }
+}
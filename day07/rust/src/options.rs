@@ -0,0 +1,56 @@
+pub struct Options {
+    pub input_path: String,
+    pub show_exits: bool,
+    pub side_exits: bool,
+    pub probabilities: bool,
+    pub weights_path: Option<String>,
+    // `--modulus` wins over `--big` when both are given.
+    pub modulus: Option<u64>,
+    pub big: bool,
+    pub heatmap: bool,
+    pub ppm_path: Option<String>,
+}
+
+impl Options {
+    // Recognises `--input PATH`, `--exits`, `--side-exits`, `--probabilities`,
+    // `--weights FILE` (which implies `--probabilities`), `--big`, `--modulus P`,
+    // `--heatmap` and `--ppm PATH`. Unspecified options keep the puzzle's
+    // defaults.
+    pub fn from_args(args: &[String]) -> Self {
+        let mut options = Options {
+            input_path: "../input.txt".to_string(),
+            show_exits: false,
+            side_exits: false,
+            probabilities: false,
+            weights_path: None,
+            modulus: None,
+            big: false,
+            heatmap: false,
+            ppm_path: None,
+        };
+
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let mut value = || {
+                iter.next()
+                    .unwrap_or_else(|| panic!("Missing value for {}", flag))
+            };
+            match flag.as_str() {
+                "--input" => options.input_path = value().to_string(),
+                "--exits" => options.show_exits = true,
+                "--side-exits" => options.side_exits = true,
+                "--probabilities" => options.probabilities = true,
+                "--weights" => {
+                    options.weights_path = Some(value().to_string());
+                    options.probabilities = true;
+                }
+                "--big" => options.big = true,
+                "--modulus" => options.modulus = Some(value().parse().expect("Invalid modulus")),
+                "--heatmap" => options.heatmap = true,
+                "--ppm" => options.ppm_path = Some(value().to_string()),
+                other => panic!("Unknown argument: {}", other),
+            }
+        }
+        options
+    }
+}
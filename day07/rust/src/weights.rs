@@ -0,0 +1,72 @@
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Zero};
+use std::collections::HashMap;
+
+// Branch probabilities for splitters: for `^` and `-` the first branch is the
+// left one, for `|` the upper one. A splitter whose two weights sum to less
+// than one loses the rest of the beam. Splitters that are not listed split
+// evenly.
+pub struct SplitterWeights {
+    even: (BigRational, BigRational),
+    by_cell: HashMap<(usize, usize), (BigRational, BigRational)>,
+}
+
+impl SplitterWeights {
+    pub fn even() -> Self {
+        let half = BigRational::new(BigInt::one(), BigInt::from(2));
+        SplitterWeights {
+            even: (half.clone(), half),
+            by_cell: HashMap::new(),
+        }
+    }
+
+    // Reads a sidecar file with one splitter per line, `row col first second`,
+    // counted from zero. Weights are fractions (`1/3`) or decimals (`0.25`);
+    // blank lines and lines starting with `#` are skipped.
+    pub fn parse(text: &str) -> Self {
+        let mut weights = SplitterWeights::even();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [row, col, first, second] = fields[..] else {
+                panic!("Line {}: expected `row col first second`", index + 1);
+            };
+            let cell = (
+                row.parse()
+                    .unwrap_or_else(|_| panic!("Line {}: invalid row", index + 1)),
+                col.parse()
+                    .unwrap_or_else(|_| panic!("Line {}: invalid column", index + 1)),
+            );
+            let first = parse_probability(first)
+                .unwrap_or_else(|| panic!("Line {}: invalid weight", index + 1));
+            let second = parse_probability(second)
+                .unwrap_or_else(|| panic!("Line {}: invalid weight", index + 1));
+            if &first + &second > BigRational::one() {
+                panic!("Line {}: weights add up to more than 1", index + 1);
+            }
+            weights.by_cell.insert(cell, (first, second));
+        }
+        weights
+    }
+
+    pub fn at(&self, cell: (usize, usize)) -> (&BigRational, &BigRational) {
+        let (first, second) = self.by_cell.get(&cell).unwrap_or(&self.even);
+        (first, second)
+    }
+}
+
+fn parse_probability(text: &str) -> Option<BigRational> {
+    let value = match text.split_once('.') {
+        Some((whole, fraction)) => {
+            let digits = format!("{}{}", whole, fraction);
+            let scale = BigInt::from(10).pow(fraction.len() as u32);
+            BigRational::new(digits.parse().ok()?, scale)
+        }
+        None => text.parse().ok()?,
+    };
+    (value >= BigRational::zero()).then_some(value)
+}
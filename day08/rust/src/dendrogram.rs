@@ -0,0 +1,260 @@
+use crate::edges::Edge;
+use crate::metric::Metric;
+use crate::union_find::UnionFind;
+use std::mem;
+
+// One merge of two clusters. Clusters are numbered as in SciPy's linkage
+// matrices: boxes are clusters `0..n`, and merge `k` creates cluster `n + k`.
+pub struct Merge {
+    pub left: usize,
+    pub right: usize,
+    pub distance: i128,
+    pub size: usize,
+}
+
+// The single-linkage merge history. Connecting the closest pair of circuits
+// again and again only ever uses spanning tree edges, so the tree's edges in
+// sorted order are exactly the merges.
+pub struct Dendrogram {
+    metric: Metric,
+    leaves: usize,
+    merges: Vec<Merge>,
+}
+
+impl Dendrogram {
+    pub fn from_spanning_tree(leaves: usize, sorted_edges: &[Edge], metric: Metric) -> Self {
+        let mut union_find = UnionFind::new(leaves);
+        // The cluster each union-find root currently stands for.
+        let mut cluster: Vec<usize> = (0..leaves).collect();
+        let mut size = vec![1; leaves];
+        let mut merges = Vec::new();
+
+        for &(distance, i, j) in sorted_edges {
+            let (root_i, root_j) = (union_find.find(i), union_find.find(j));
+            if !union_find.union(i, j) {
+                continue;
+            }
+            let merged = size[root_i] + size[root_j];
+            merges.push(Merge {
+                left: cluster[root_i],
+                right: cluster[root_j],
+                distance,
+                size: merged,
+            });
+            let root = union_find.find(i);
+            cluster[root] = leaves + merges.len() - 1;
+            size[root] = merged;
+        }
+        Dendrogram {
+            metric,
+            leaves,
+            merges,
+        }
+    }
+
+    // Newick with boxes labelled by index and branch lengths as differences in
+    // merge distance. A forest of several trees is joined under one unlabelled
+    // root.
+    pub fn to_newick(&self) -> String {
+        let roots = self.roots();
+        let mut out = String::new();
+        if roots.len() > 1 {
+            out.push('(');
+        }
+        for (index, &root) in roots.iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            self.write_newick(root, &mut out);
+        }
+        if roots.len() > 1 {
+            out.push(')');
+        }
+        out.push(';');
+        out
+    }
+
+    // Walks the tree with an explicit stack, since chains of merges can be as
+    // deep as there are boxes.
+    fn write_newick(&self, root: usize, out: &mut String) {
+        // Clusters carry their parent's height; the root has none, so it gets
+        // no branch length.
+        enum Visit {
+            Enter(usize, Option<i128>),
+            Between,
+            Leave(usize, Option<i128>),
+        }
+
+        let length = |cluster: usize, parent_height: Option<i128>| match parent_height {
+            Some(height) => format!(":{}", self.branch_length(height, self.height(cluster))),
+            None => String::new(),
+        };
+        let mut stack = vec![Visit::Enter(root, None)];
+        while let Some(visit) = stack.pop() {
+            match visit {
+                Visit::Enter(cluster, parent_height) if cluster < self.leaves => {
+                    out.push_str(&format!("{}{}", cluster, length(cluster, parent_height)));
+                }
+                Visit::Enter(cluster, parent_height) => {
+                    let merge = &self.merges[cluster - self.leaves];
+                    out.push('(');
+                    stack.push(Visit::Leave(cluster, parent_height));
+                    stack.push(Visit::Enter(merge.right, Some(merge.distance)));
+                    stack.push(Visit::Between);
+                    stack.push(Visit::Enter(merge.left, Some(merge.distance)));
+                }
+                Visit::Between => out.push(','),
+                Visit::Leave(cluster, parent_height) => {
+                    out.push(')');
+                    out.push_str(&length(cluster, parent_height));
+                }
+            }
+        }
+    }
+
+    // The distance a cluster formed at; boxes start at zero.
+    fn height(&self, cluster: usize) -> i128 {
+        if cluster < self.leaves {
+            0
+        } else {
+            self.merges[cluster - self.leaves].distance
+        }
+    }
+
+    // Heights are stored as the metric measures them, squared for the Euclidean
+    // metrics, so those are rooted before export. Exact integer differences are
+    // kept for the others.
+    fn branch_length(&self, parent: i128, child: i128) -> String {
+        if self.metric.is_squared() {
+            (root(parent) - root(child)).to_string()
+        } else {
+            (parent - child).to_string()
+        }
+    }
+
+    // Merges with their true distance; for the Euclidean metrics the exact
+    // squared distance goes alongside as `squared_distance`.
+    pub fn to_json(&self) -> String {
+        let merges: Vec<String> = self
+            .merges
+            .iter()
+            .map(|m| {
+                let distance = if self.metric.is_squared() {
+                    format!(
+                        "\"distance\":{},\"squared_distance\":{}",
+                        root(m.distance),
+                        m.distance
+                    )
+                } else {
+                    format!("\"distance\":{}", m.distance)
+                };
+                format!(
+                    "{{\"left\":{},\"right\":{},{},\"size\":{}}}",
+                    m.left, m.right, distance, m.size
+                )
+            })
+            .collect();
+        format!(
+            "{{\"leaves\":{},\"merges\":[{}]}}",
+            self.leaves,
+            merges.join(",")
+        )
+    }
+
+    // The clusters left after every merge at or below `distance`.
+    pub fn cut_at_distance(&self, distance: i128) -> Vec<Vec<usize>> {
+        self.clusters_after(
+            self.merges
+                .iter()
+                .take_while(|m| m.distance <= distance)
+                .count(),
+        )
+    }
+
+    // The clusters left once merging is down to `count` of them.
+    pub fn cut_into(&self, count: usize) -> Vec<Vec<usize>> {
+        self.clusters_after(
+            self.leaves
+                .saturating_sub(count.max(1))
+                .min(self.merges.len()),
+        )
+    }
+
+    // The members of each cluster after the first `merges` merges, each sorted,
+    // ordered by their smallest member.
+    fn clusters_after(&self, merges: usize) -> Vec<Vec<usize>> {
+        let mut members: Vec<Vec<usize>> = (0..self.leaves).map(|leaf| vec![leaf]).collect();
+        for merge in &self.merges[..merges] {
+            let mut joined = mem::take(&mut members[merge.left]);
+            let mut other = mem::take(&mut members[merge.right]);
+            if joined.len() < other.len() {
+                mem::swap(&mut joined, &mut other);
+            }
+            joined.append(&mut other);
+            members.push(joined);
+        }
+        let mut clusters: Vec<Vec<usize>> = members.into_iter().filter(|m| !m.is_empty()).collect();
+        for cluster in &mut clusters {
+            cluster.sort_unstable();
+        }
+        clusters.sort();
+        clusters
+    }
+
+    fn roots(&self) -> Vec<usize> {
+        let mut merged = vec![false; self.leaves + self.merges.len()];
+        for merge in &self.merges {
+            merged[merge.left] = true;
+            merged[merge.right] = true;
+        }
+        (0..merged.len()).filter(|&c| !merged[c]).collect()
+    }
+}
+
+fn root(squared: i128) -> f64 {
+    (squared as f64).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Boxes on a line at 0, 1, 10, 12 and 30.
+    fn line(metric: Metric) -> Dendrogram {
+        let edges = [(1, 0, 1), (2, 2, 3), (9, 1, 2), (18, 3, 4)]
+            .map(|(distance, i, j)| (metric.threshold(distance), i, j));
+        Dendrogram::from_spanning_tree(5, &edges, metric)
+    }
+
+    #[test]
+    fn newick_uses_true_distances() {
+        let expected = "(((0:1,1:1):8,(2:2,3:2):7):9,4:18);";
+        assert_eq!(line(Metric::Euclidean).to_newick(), expected);
+        assert_eq!(line(Metric::Manhattan).to_newick(), expected);
+    }
+
+    #[test]
+    fn json_keeps_exact_squares_alongside() {
+        let json = line(Metric::Euclidean).to_json();
+        assert!(
+            json.contains(r#"{"left":5,"right":6,"distance":9,"squared_distance":81,"size":4}"#)
+        );
+        assert!(
+            !line(Metric::Chebyshev)
+                .to_json()
+                .contains("squared_distance")
+        );
+    }
+
+    #[test]
+    fn cuts() {
+        let dendrogram = line(Metric::Euclidean);
+        assert_eq!(
+            dendrogram.cut_at_distance(Metric::Euclidean.threshold(8)),
+            [vec![0, 1], vec![2, 3], vec![4]]
+        );
+        assert_eq!(dendrogram.cut_into(2), [vec![0, 1, 2, 3], vec![4]]);
+        assert_eq!(dendrogram.cut_into(1), [vec![0, 1, 2, 3, 4]]);
+        assert_eq!(dendrogram.cut_into(9).len(), 5);
+    }
+}
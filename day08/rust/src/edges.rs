@@ -0,0 +1,102 @@
+use crate::kdtree::KdTree;
+use crate::union_find::UnionFind;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+// An edge as (distance, lower index, higher index), with the distance as the
+// metric measures it (squared for the Euclidean ones). Edges compare as
+// tuples: by exact distance, and between equal distances by the lower box index
+// and then the higher one, so every run connects boxes in the same order.
+pub type Edge = (i128, usize, usize);
+
+// Edges in the order a full sort would give them (by distance, then by
+// indices), produced on demand. Every edge belongs to its lower-indexed box;
+// the heap holds each box's next edge, and taking one looks up the box's
+// following neighbour.
+pub struct ShortestEdges<'a> {
+    tree: &'a KdTree,
+    heads: BinaryHeap<Reverse<Edge>>,
+}
+
+impl<'a> ShortestEdges<'a> {
+    pub fn new(tree: &'a KdTree) -> Self {
+        let heads = tree
+            .spatial_order()
+            .iter()
+            .filter_map(|&a| Some(Reverse(tree.nearest_later(a, None)?)))
+            .collect();
+        ShortestEdges { tree, heads }
+    }
+}
+
+impl Iterator for ShortestEdges<'_> {
+    type Item = Edge;
+
+    fn next(&mut self) -> Option<Edge> {
+        let Reverse(edge) = self.heads.pop()?;
+        let (distance, a, b) = edge;
+
+        if let Some(following) = self.tree.nearest_later(a, Some((distance, b))) {
+            self.heads.push(Reverse(following));
+        }
+        Some(edge)
+    }
+}
+
+// The minimum spanning tree under the tree's metric by Borůvka's algorithm: each round every
+// component joins its nearest other component, found through the tree with
+// whole subtrees of the component skipped. Edges come back sorted.
+pub fn minimum_spanning_tree(tree: &KdTree) -> Vec<Edge> {
+    let mut union_find = UnionFind::new(tree.len());
+    let mut edges: Vec<Edge> = Vec::new();
+    // Each box's nearest box outside its component as of the last search.
+    // Components only grow, so while that box is still outside it is still the
+    // nearest, and its distance is a lower bound once it is not.
+    let mut nearest: Vec<Option<Edge>> = vec![None; tree.len()];
+
+    while union_find.components() > 1 {
+        let component: Vec<usize> = (0..tree.len()).map(|i| union_find.find(i)).collect();
+        let uniform = tree.uniform_components(&component);
+
+        // Boxes whose nearest outside box is still outside go first, so most
+        // components have a bound before any searching starts.
+        let mut best: Vec<Option<Edge>> = vec![None; tree.len()];
+        let mut stale: Vec<usize> = Vec::new();
+        for &query in tree.spatial_order() {
+            match nearest[query] {
+                Some(previous) if component[previous.1] != component[previous.2] => {
+                    let best = &mut best[component[query]];
+                    if best.is_none_or(|best| previous < best) {
+                        *best = Some(previous);
+                    }
+                }
+                _ => stale.push(query),
+            }
+        }
+
+        for query in stale {
+            let best = &mut best[component[query]];
+            if let (Some(previous), Some(bound)) = (nearest[query], *best)
+                && previous.0 > bound.0
+            {
+                continue;
+            }
+            // Searching within the component's best so far: anything found
+            // beats it and is this box's true nearest.
+            let bound = *best;
+            tree.nearest_outside(query, &component, &uniform, best);
+            if *best != bound {
+                nearest[query] = *best;
+            }
+        }
+
+        for &(distance, a, b) in best.iter().flatten() {
+            if union_find.union(a, b) {
+                edges.push((distance, a, b));
+            }
+        }
+    }
+
+    edges.sort();
+    edges
+}
@@ -0,0 +1,220 @@
+use crate::edges::Edge;
+use crate::metric::Metric;
+
+const LEAF_SIZE: usize = 8;
+
+// Coordinates are limited to this magnitude so that squared distances are
+// exact in an `i128`: each difference stays below 2^62 and three squares below
+// 2^126.
+pub const MAX_COORDINATE: i64 = 1 << 61;
+
+// A node covers `order[start..end]` and knows the bounding box of those points.
+struct Node {
+    lo: [i64; 3],
+    hi: [i64; 3],
+    start: usize,
+    end: usize,
+    // The highest point index in the node.
+    max_index: usize,
+    children: Option<(usize, usize)>,
+}
+
+pub struct KdTree {
+    metric: Metric,
+    points: Vec<[i64; 3]>,
+    order: Vec<usize>,
+    // The points again, in `order`, so a leaf's points sit next to each other.
+    ordered_points: Vec<[i64; 3]>,
+    // Nodes in preorder, so children always come after their parent.
+    nodes: Vec<Node>,
+}
+
+impl KdTree {
+    pub fn new(boxes: &[(i64, i64, i64)], metric: Metric) -> Self {
+        let mut tree = KdTree {
+            metric,
+            points: boxes.iter().map(|&(x, y, z)| [x, y, z]).collect(),
+            order: (0..boxes.len()).collect(),
+            ordered_points: Vec::new(),
+            nodes: Vec::new(),
+        };
+        if !boxes.is_empty() {
+            tree.build(0, boxes.len());
+        }
+        tree.ordered_points = tree.order.iter().map(|&index| tree.points[index]).collect();
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    // Point indices in tree order, which keeps nearby points together.
+    pub fn spatial_order(&self) -> &[usize] {
+        &self.order
+    }
+
+    // Splits on the widest axis at the median until the leaves are small.
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let mut lo = [i64::MAX; 3];
+        let mut hi = [i64::MIN; 3];
+        for &index in &self.order[start..end] {
+            for axis in 0..3 {
+                lo[axis] = lo[axis].min(self.points[index][axis]);
+                hi[axis] = hi[axis].max(self.points[index][axis]);
+            }
+        }
+
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            lo,
+            hi,
+            start,
+            end,
+            max_index: self.order[start..end].iter().copied().max().unwrap(),
+            children: None,
+        });
+        if end - start > LEAF_SIZE {
+            let axis = (0..3).max_by_key(|&axis| hi[axis] - lo[axis]).unwrap();
+            let middle = start + (end - start) / 2;
+            let points = &self.points;
+            self.order[start..end]
+                .select_nth_unstable_by_key(middle - start, |&index| points[index][axis]);
+            let left = self.build(start, middle);
+            let right = self.build(middle, end);
+            self.nodes[node].children = Some((left, right));
+        }
+        node
+    }
+
+    // The smallest distance from `query` to anything in the node's box.
+    fn box_distance(&self, node: usize, query: [i64; 3]) -> i128 {
+        let Node { lo, hi, .. } = self.nodes[node];
+        self.metric.measure(
+            [0, 1, 2]
+                .map(|axis| (lo[axis] - query[axis]).max(query[axis] - hi[axis]).max(0) as i128),
+        )
+    }
+
+    // For each node, the component all of its points belong to, if they share
+    // one.
+    pub fn uniform_components(&self, component: &[usize]) -> Vec<Option<usize>> {
+        let mut uniform = vec![None; self.nodes.len()];
+        for node in (0..self.nodes.len()).rev() {
+            let Node {
+                start,
+                end,
+                children,
+                ..
+            } = self.nodes[node];
+            uniform[node] = match children {
+                Some((left, right)) if uniform[left] == uniform[right] => uniform[left],
+                Some(_) => None,
+                None => {
+                    let first = component[self.order[start]];
+                    self.order[start..end]
+                        .iter()
+                        .all(|&index| component[index] == first)
+                        .then_some(first)
+                }
+            };
+        }
+        uniform
+    }
+
+    // The closest point with a higher index than `query` that comes after
+    // `after`, a (distance, index) pair, in order of distance and then index.
+    // Stepping `after` through the results walks a point's later neighbours.
+    pub fn nearest_later(&self, query: usize, after: Option<(i128, usize)>) -> Option<Edge> {
+        let position = self.points[query];
+        let skip = |node: usize| {
+            self.nodes[node].max_index <= query
+                || after
+                    .is_some_and(|(distance, _)| self.farthest_distance(node, position) < distance)
+        };
+        let accept = |distance: i128, other: usize| {
+            other > query && after.is_none_or(|after| (distance, other) > after)
+        };
+        let mut best = None;
+        self.search(0, 0, query, &skip, &accept, &mut best);
+        best
+    }
+
+    // The largest distance from `query` to anything in the node's box.
+    fn farthest_distance(&self, node: usize, query: [i64; 3]) -> i128 {
+        let Node { lo, hi, .. } = self.nodes[node];
+        self.metric.measure(
+            [0, 1, 2].map(|axis| (query[axis] - lo[axis]).max(hi[axis] - query[axis]) as i128),
+        )
+    }
+
+    // Lowers `best` to the closest point to `query` outside its component, if
+    // any is closer.
+    pub fn nearest_outside(
+        &self,
+        query: usize,
+        component: &[usize],
+        uniform: &[Option<usize>],
+        best: &mut Option<Edge>,
+    ) {
+        let own = component[query];
+        self.search(
+            0,
+            0,
+            query,
+            &|node| uniform[node] == Some(own),
+            &|_, other| component[other] != own,
+            best,
+        );
+    }
+
+    // Depth-first search for the closest accepted point, skipping nodes that
+    // cannot hold one. Ties go to the lower pair of indices.
+    fn search(
+        &self,
+        node: usize,
+        node_distance: i128,
+        query: usize,
+        skip: &impl Fn(usize) -> bool,
+        accept: &impl Fn(i128, usize) -> bool,
+        best: &mut Option<Edge>,
+    ) {
+        if self.nodes.is_empty()
+            || skip(node)
+            || best.is_some_and(|(distance, _, _)| node_distance > distance)
+        {
+            return;
+        }
+        let position = self.points[query];
+
+        match self.nodes[node].children {
+            Some((left, right)) => {
+                let left_distance = self.box_distance(left, position);
+                let right_distance = self.box_distance(right, position);
+                let mut children = [(left, left_distance), (right, right_distance)];
+                if right_distance < left_distance {
+                    children.swap(0, 1);
+                }
+                for (child, distance) in children {
+                    self.search(child, distance, query, skip, accept, best);
+                }
+            }
+            None => {
+                let Node { start, end, .. } = self.nodes[node];
+                for (&other, &point) in self.order[start..end]
+                    .iter()
+                    .zip(&self.ordered_points[start..end])
+                {
+                    let distance = self.metric.between(position, point);
+                    if other == query || !accept(distance, other) {
+                        continue;
+                    }
+                    let candidate = (distance, query.min(other), query.max(other));
+                    if best.is_none_or(|current| candidate < current) {
+                        *best = Some(candidate);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,114 +1,106 @@
-use std::collections::HashMap;
+mod dendrogram;
+mod edges;
+mod kdtree;
+mod metric;
+mod options;
+mod union_find;
+
+use dendrogram::Dendrogram;
+use edges::{Edge, ShortestEdges, minimum_spanning_tree};
+use kdtree::{KdTree, MAX_COORDINATE};
+use options::{Budget, Cut, DendrogramFormat, Options};
+use std::env;
 use std::fs;
-
-struct UnionFind {
-    parent: Vec<usize>,
-    rank: Vec<usize>,
-    component_count: usize,
-}
-
-impl UnionFind {
-    fn new(size: usize) -> Self {
-        UnionFind {
-            parent: (0..size).collect(),
-            rank: vec![0; size],
-            component_count: size,
-        }
-    }
-
-    fn find(&mut self, x: usize) -> usize {
-        if self.parent[x] != x {
-            self.parent[x] = self.find(self.parent[x]);
-        }
-        self.parent[x]
-    }
-
-    fn union(&mut self, x: usize, y: usize) -> bool {
-        let root_x = self.find(x);
-        let root_y = self.find(y);
-
-        if root_x != root_y {
-            if self.rank[root_x] < self.rank[root_y] {
-                self.parent[root_x] = root_y;
-            } else if self.rank[root_x] > self.rank[root_y] {
-                self.parent[root_y] = root_x;
-            } else {
-                self.parent[root_y] = root_x;
-                self.rank[root_x] += 1;
-            }
-            self.component_count -= 1;
-            true
-        } else {
-            false
-        }
-    }
-
-    fn components(&self) -> usize {
-        self.component_count
-    }
-
-    fn component_sizes(&mut self) -> HashMap<usize, usize> {
-        let mut sizes: HashMap<usize, usize> = HashMap::new();
-        for i in 0..self.parent.len() {
-            let root = self.find(i);
-            *sizes.entry(root).or_insert(0) += 1;
-        }
-        sizes
-    }
-}
+use union_find::UnionFind;
 
 fn main() {
-    let input = fs::read_to_string("../input.txt").expect("Failed to read input file");
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = Options::from_args(&args);
+
+    let input = fs::read_to_string(&options.input_path).expect("Failed to read input file");
     let boxes: Vec<(i64, i64, i64)> = input
         .lines()
         .filter(|l| !l.is_empty())
         .map(|line| {
             let parts: Vec<i64> = line.split(',').map(|s| s.parse().unwrap()).collect();
+            assert!(
+                parts
+                    .iter()
+                    .all(|c| c.unsigned_abs() <= MAX_COORDINATE as u64),
+                "Coordinate out of range: {}",
+                line
+            );
             (parts[0], parts[1], parts[2])
         })
         .collect();
 
-    let edges = build_sorted_edges(&boxes);
-
-    println!("Part 1: {}", find_top_three_component_product(boxes.len(), &edges));
-    println!("Part 2: {}", find_last_connection_product(&boxes, &edges));
-}
-
-fn build_sorted_edges(boxes: &[(i64, i64, i64)]) -> Vec<(f64, usize, usize)> {
-    let num_boxes = boxes.len();
-    let mut edges: Vec<(f64, usize, usize)> = Vec::new();
-
-    for i in 0..num_boxes {
-        for j in (i + 1)..num_boxes {
-            let (x1, y1, z1) = boxes[i];
-            let (x2, y2, z2) = boxes[j];
-            let dx = (x2 - x1) as f64;
-            let dy = (y2 - y1) as f64;
-            let dz = (z2 - z1) as f64;
-            let distance_squared = dx * dx + dy * dy + dz * dz;
-            edges.push((distance_squared, i, j));
+    let tree = KdTree::new(&boxes, options.metric);
+
+    if options.dendrogram.is_some() || options.cut.is_some() {
+        let dendrogram = Dendrogram::from_spanning_tree(
+            boxes.len(),
+            &minimum_spanning_tree(&tree),
+            options.metric,
+        );
+        match options.dendrogram {
+            Some(DendrogramFormat::Newick) => println!("{}", dendrogram.to_newick()),
+            Some(DendrogramFormat::Json) => println!("{}", dendrogram.to_json()),
+            None => {}
         }
+        let clusters = match options.cut {
+            Some(Cut::AtDistance(distance)) => {
+                dendrogram.cut_at_distance(options.metric.threshold(distance))
+            }
+            Some(Cut::IntoClusters(count)) => dendrogram.cut_into(count),
+            None => return,
+        };
+        print_clusters(&boxes, &clusters);
+        return;
     }
-
-    edges.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
-    edges
+    println!(
+        "Part 1: {}",
+        combine_largest_components(boxes.len(), ShortestEdges::new(&tree), &options)
+    );
+    println!(
+        "Part 2: {}",
+        find_last_connection_product(&boxes, &minimum_spanning_tree(&tree))
+    );
 }
 
-fn find_top_three_component_product(num_boxes: usize, sorted_edges: &[(f64, usize, usize)]) -> i64 {
+fn combine_largest_components(
+    num_boxes: usize,
+    sorted_edges: impl Iterator<Item = Edge>,
+    options: &Options,
+) -> String {
     let mut union_find = UnionFind::new(num_boxes);
 
-    // Connect boxes using the N shortest edges
-    for &(_, i, j) in sorted_edges.iter().take(num_boxes) {
+    // Connect boxes using the shortest edges the budget allows
+    let chosen: Box<dyn Iterator<Item = Edge>> = match options.budget {
+        None => Box::new(sorted_edges.take(num_boxes)),
+        Some(Budget::Connections(count)) => Box::new(sorted_edges.take(count)),
+        Some(Budget::Within(distance)) => {
+            let limit = options.metric.threshold(distance);
+            Box::new(sorted_edges.take_while(move |&(d, _, _)| d <= limit))
+        }
+    };
+    for (_, i, j) in chosen {
         union_find.union(i, j);
     }
 
-    let mut sizes: Vec<i64> = union_find.component_sizes().values().map(|&v| v as i64).collect();
+    let mut sizes: Vec<i64> = union_find
+        .component_sizes()
+        .values()
+        .map(|&v| v as i64)
+        .collect();
     sizes.sort_by(|a, b| b.cmp(a));
+    sizes.truncate(options.top);
 
-    sizes.iter().take(3).product()
+    options.combine.apply(&sizes)
 }
 
-fn find_last_connection_product(boxes: &[(i64, i64, i64)], sorted_edges: &[(f64, usize, usize)]) -> i64 {
+// Only the edges of the minimum spanning tree ever join two circuits, so those
+// are enough here.
+fn find_last_connection_product(boxes: &[(i64, i64, i64)], sorted_edges: &[Edge]) -> i128 {
     let mut union_find = UnionFind::new(boxes.len());
     let mut last_edge_boxes: (usize, usize) = (0, 0);
 
@@ -122,5 +114,130 @@ fn find_last_connection_product(boxes: &[(i64, i64, i64)], sorted_edges: &[(f64,
     }
 
     let (last_i, last_j) = last_edge_boxes;
-    boxes[last_i].0 * boxes[last_j].0
+    boxes[last_i].0 as i128 * boxes[last_j].0 as i128
+}
+
+fn print_clusters(boxes: &[(i64, i64, i64)], clusters: &[Vec<usize>]) {
+    println!("{} clusters", clusters.len());
+    for (index, members) in clusters.iter().enumerate() {
+        let listed: Vec<String> = members
+            .iter()
+            .map(|&i| format!("{} ({},{},{})", i, boxes[i].0, boxes[i].1, boxes[i].2))
+            .collect();
+        println!(
+            "Cluster {} ({} boxes): {}",
+            index + 1,
+            members.len(),
+            listed.join(", ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metric::Metric;
+
+    // Every pair, sorted: the reference the k-d tree has to agree with.
+    fn build_sorted_edges(boxes: &[(i64, i64, i64)], metric: &Metric) -> Vec<Edge> {
+        let num_boxes = boxes.len();
+        let mut edges: Vec<Edge> = Vec::new();
+
+        for i in 0..num_boxes {
+            for j in (i + 1)..num_boxes {
+                let (x1, y1, z1) = boxes[i];
+                let (x2, y2, z2) = boxes[j];
+                edges.push((metric.between([x1, y1, z1], [x2, y2, z2]), i, j));
+            }
+        }
+
+        edges.sort();
+        edges
+    }
+
+    // Kruskal's algorithm over the full edge list.
+    fn spanning_tree_by_sorting(num_boxes: usize, sorted_edges: &[Edge]) -> Vec<Edge> {
+        let mut union_find = UnionFind::new(num_boxes);
+        sorted_edges
+            .iter()
+            .copied()
+            .filter(|&(_, i, j)| union_find.union(i, j))
+            .collect()
+    }
+
+    // The k-d tree has too many tie and pruning paths to cover with a table,
+    // so cases come from a seeded xorshift.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, bound: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % bound
+        }
+    }
+
+    // Few distinct coordinates, so many distances tie and some boxes coincide.
+    fn random_boxes(rng: &mut Rng, spread: i64, scale: i64) -> Vec<(i64, i64, i64)> {
+        let count = 1 + rng.below(60) as usize;
+        let mut coordinate = || (rng.below(spread as u64) as i64 - spread / 2) * scale;
+        (0..count)
+            .map(|_| (coordinate(), coordinate(), coordinate()))
+            .collect()
+    }
+
+    fn check_against_sorting(boxes: &[(i64, i64, i64)], metric: Metric) {
+        let sorted = build_sorted_edges(boxes, &metric);
+        let tree = KdTree::new(boxes, metric);
+
+        let lazy: Vec<Edge> = ShortestEdges::new(&tree).collect();
+        assert_eq!(lazy, sorted, "{:?} {:?}", metric, boxes);
+
+        let spanning_tree = minimum_spanning_tree(&tree);
+        assert_eq!(
+            spanning_tree,
+            spanning_tree_by_sorting(boxes.len(), &sorted),
+            "{:?} {:?}",
+            metric,
+            boxes
+        );
+
+        let options = Options::from_args(&[]);
+        assert_eq!(
+            combine_largest_components(boxes.len(), ShortestEdges::new(&tree), &options),
+            combine_largest_components(boxes.len(), sorted.iter().copied(), &options)
+        );
+        assert_eq!(
+            find_last_connection_product(boxes, &spanning_tree),
+            find_last_connection_product(boxes, &sorted)
+        );
+    }
+
+    #[test]
+    fn kd_tree_matches_sorting_all_pairs() {
+        let metrics = [
+            Metric::Euclidean,
+            Metric::Manhattan,
+            Metric::Chebyshev,
+            Metric::WeightedEuclidean([1, 3, 2]),
+        ];
+        let mut rng = Rng(0x5eed);
+        for _ in 0..150 {
+            let spread = 2 + rng.below(8) as i64;
+            let boxes = random_boxes(&mut rng, spread, 1);
+            for metric in metrics {
+                check_against_sorting(&boxes, metric);
+            }
+        }
+    }
+
+    #[test]
+    fn kd_tree_matches_sorting_at_coordinate_limit() {
+        let mut rng = Rng(0xb0c5);
+        for _ in 0..20 {
+            let boxes = random_boxes(&mut rng, 3, MAX_COORDINATE);
+            check_against_sorting(&boxes, Metric::Euclidean);
+        }
+    }
 }
@@ -0,0 +1,102 @@
+// How far apart two boxes are. Only the order of distances matters, so the
+// Euclidean metrics measure squared distance and everything stays an exact
+// integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    Euclidean,
+    Manhattan,
+    Chebyshev,
+    // Euclidean with each axis's squared difference scaled by a weight.
+    WeightedEuclidean([i128; 3]),
+}
+
+impl Metric {
+    // `euclidean`, `manhattan`, `chebyshev` or `weighted:wx,wy,wz` with positive
+    // integer weights.
+    pub fn parse(spec: &str) -> Self {
+        match spec {
+            "euclidean" => Metric::Euclidean,
+            "manhattan" => Metric::Manhattan,
+            "chebyshev" => Metric::Chebyshev,
+            _ => {
+                let weights = spec
+                    .strip_prefix("weighted:")
+                    .unwrap_or_else(|| panic!("Unknown metric: {}", spec));
+                let weights: Vec<i128> = weights
+                    .split(',')
+                    .map(|w| w.trim().parse().expect("Invalid weight"))
+                    .collect();
+                match weights[..] {
+                    [x, y, z] if x > 0 && y > 0 && z > 0 => Metric::WeightedEuclidean([x, y, z]),
+                    _ => panic!("Weights must be three positive integers: {}", spec),
+                }
+            }
+        }
+    }
+
+    pub fn between(&self, a: [i64; 3], b: [i64; 3]) -> i128 {
+        self.measure([0, 1, 2].map(|axis| (b[axis] as i128 - a[axis] as i128).abs()))
+    }
+
+    // The distance for the given absolute differences along each axis. It never
+    // decreases when a difference grows, so applying it to the gaps between a
+    // point and a box bounds the distance to anything in the box.
+    pub fn measure(&self, gaps: [i128; 3]) -> i128 {
+        match self {
+            Metric::Euclidean => gaps.iter().map(|d| d * d).sum(),
+            Metric::Manhattan => gaps.iter().sum(),
+            Metric::Chebyshev => gaps.into_iter().max().unwrap(),
+            Metric::WeightedEuclidean(weights) => gaps
+                .iter()
+                .zip(weights)
+                .map(|(d, w)| (d * d).checked_mul(*w))
+                .try_fold(0i128, |total, term| total.checked_add(term?))
+                .expect("Weighted distance overflows i128"),
+        }
+    }
+
+    // What `measure` gives for a plain distance, for comparing against
+    // thresholds. A square past `i128::MAX` saturates, which still lies beyond
+    // every distance `measure` can return.
+    pub fn threshold(&self, distance: i128) -> i128 {
+        match self {
+            Metric::Euclidean | Metric::WeightedEuclidean(_) => distance.saturating_mul(distance),
+            Metric::Manhattan | Metric::Chebyshev => distance,
+        }
+    }
+
+    // Whether `measure` gives the square of the distance.
+    pub fn is_squared(&self) -> bool {
+        matches!(self, Metric::Euclidean | Metric::WeightedEuclidean(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds() {
+        assert_eq!(Metric::Euclidean.threshold(8), 64);
+        assert_eq!(Metric::WeightedEuclidean([1, 2, 3]).threshold(8), 64);
+        assert_eq!(Metric::Manhattan.threshold(8), 8);
+        assert_eq!(Metric::Chebyshev.threshold(8), 8);
+    }
+
+    #[test]
+    fn huge_thresholds_saturate() {
+        assert_eq!(
+            Metric::Euclidean.threshold(14_000_000_000_000_000_000),
+            i128::MAX
+        );
+        assert_eq!(
+            Metric::Euclidean.threshold(20_000_000_000_000_000_000),
+            i128::MAX
+        );
+        assert_eq!(
+            Metric::Euclidean.threshold(-20_000_000_000_000_000_000),
+            i128::MAX
+        );
+        assert_eq!(Metric::Manhattan.threshold(i128::MAX), i128::MAX);
+    }
+}
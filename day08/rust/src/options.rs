@@ -0,0 +1,150 @@
+use crate::metric::Metric;
+
+// Which of the shortest edges get connected.
+pub enum Budget {
+    // The given number of shortest edges.
+    Connections(usize),
+    // Every edge no longer than the given distance.
+    Within(i128),
+}
+
+// How the sizes of the largest circuits are reported.
+pub enum Combine {
+    Product,
+    Sum,
+    List,
+}
+
+impl Combine {
+    // A product of many circuit sizes soon outgrows any integer, so it is
+    // reported as too large rather than wrapping.
+    pub fn apply(&self, sizes: &[i64]) -> String {
+        match self {
+            Combine::Product => sizes
+                .iter()
+                .try_fold(1u128, |product, &size| product.checked_mul(size as u128))
+                .map_or_else(
+                    || "product too large for u128".to_string(),
+                    |p| p.to_string(),
+                ),
+            Combine::Sum => sizes.iter().sum::<i64>().to_string(),
+            Combine::List => sizes
+                .iter()
+                .map(i64::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+pub enum DendrogramFormat {
+    Newick,
+    Json,
+}
+
+// Where to cut the dendrogram to list clusters.
+pub enum Cut {
+    AtDistance(i128),
+    IntoClusters(usize),
+}
+
+pub struct Options {
+    pub input_path: String,
+    // `None` connects as many edges as there are boxes, as the puzzle does.
+    pub budget: Option<Budget>,
+    pub top: usize,
+    pub combine: Combine,
+    pub metric: Metric,
+    pub dendrogram: Option<DendrogramFormat>,
+    pub cut: Option<Cut>,
+}
+
+impl Options {
+    // Recognises `--input PATH`, `--connections N`, `--within DISTANCE`, `--top K`,
+    // `--combine product|sum|list`, `--metric euclidean|manhattan|chebyshev|weighted:wx,wy,wz`,
+    // `--dendrogram newick|json`, `--cut-distance DISTANCE` and
+    // `--cut-clusters K`. Unspecified options keep the puzzle's defaults.
+    pub fn from_args(args: &[String]) -> Self {
+        let mut options = Options {
+            input_path: "../input.txt".to_string(),
+            budget: None,
+            top: 3,
+            combine: Combine::Product,
+            metric: Metric::Euclidean,
+            dendrogram: None,
+            cut: None,
+        };
+
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let mut value = || {
+                iter.next()
+                    .unwrap_or_else(|| panic!("Missing value for {}", flag))
+            };
+            match flag.as_str() {
+                "--input" => options.input_path = value().to_string(),
+                "--connections" => {
+                    options.budget = Some(Budget::Connections(
+                        value().parse().expect("Invalid connection count"),
+                    ))
+                }
+                "--within" => {
+                    options.budget =
+                        Some(Budget::Within(value().parse().expect("Invalid distance")))
+                }
+                "--top" => options.top = value().parse().expect("Invalid circuit count"),
+                "--combine" => {
+                    options.combine = match value().as_str() {
+                        "product" => Combine::Product,
+                        "sum" => Combine::Sum,
+                        "list" => Combine::List,
+                        other => panic!("Unknown combination: {}", other),
+                    }
+                }
+                "--metric" => options.metric = Metric::parse(value()),
+                "--dendrogram" => {
+                    options.dendrogram = match value().as_str() {
+                        "newick" => Some(DendrogramFormat::Newick),
+                        "json" => Some(DendrogramFormat::Json),
+                        other => panic!("Unknown dendrogram format: {}", other),
+                    }
+                }
+                "--cut-distance" => {
+                    options.cut = Some(Cut::AtDistance(value().parse().expect("Invalid distance")))
+                }
+                "--cut-clusters" => {
+                    options.cut = Some(Cut::IntoClusters(
+                        value().parse().expect("Invalid cluster count"),
+                    ))
+                }
+                other => panic!("Unknown argument: {}", other),
+            }
+        }
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combine_sizes() {
+        assert_eq!(Combine::Product.apply(&[5, 4, 2]), "40");
+        assert_eq!(Combine::Product.apply(&[]), "1");
+        assert_eq!(Combine::Sum.apply(&[5, 4, 2]), "11");
+        assert_eq!(Combine::List.apply(&[5, 4, 2]), "5,4,2");
+    }
+
+    #[test]
+    fn product_past_u128_is_reported() {
+        assert_eq!(
+            Combine::Product.apply(&[1 << 32; 3]),
+            (1u128 << 96).to_string()
+        );
+        assert_eq!(
+            Combine::Product.apply(&[1 << 32; 4]),
+            "product too large for u128"
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+pub struct UnionFind {
+    parent: Vec<usize>,
+    rank: Vec<usize>,
+    component_count: usize,
+}
+
+impl UnionFind {
+    pub fn new(size: usize) -> Self {
+        UnionFind {
+            parent: (0..size).collect(),
+            rank: vec![0; size],
+            component_count: size,
+        }
+    }
+
+    pub fn find(&mut self, x: usize) -> usize {
+        if self.parent[x] != x {
+            self.parent[x] = self.find(self.parent[x]);
+        }
+        self.parent[x]
+    }
+
+    pub fn union(&mut self, x: usize, y: usize) -> bool {
+        let root_x = self.find(x);
+        let root_y = self.find(y);
+
+        if root_x != root_y {
+            if self.rank[root_x] < self.rank[root_y] {
+                self.parent[root_x] = root_y;
+            } else if self.rank[root_x] > self.rank[root_y] {
+                self.parent[root_y] = root_x;
+            } else {
+                self.parent[root_y] = root_x;
+                self.rank[root_x] += 1;
+            }
+            self.component_count -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn components(&self) -> usize {
+        self.component_count
+    }
+
+    pub fn component_sizes(&mut self) -> HashMap<usize, usize> {
+        let mut sizes: HashMap<usize, usize> = HashMap::new();
+        for i in 0..self.parent.len() {
+            let root = self.find(i);
+            *sizes.entry(root).or_insert(0) += 1;
+        }
+        sizes
+    }
+}